@@ -0,0 +1,497 @@
+use ghs::generate::Family;
+use ghs::output::OutputFormat;
+use ghs::{Backend, Format};
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+const HELP: &str = "\
+ghs - distributed minimum spanning tree with the Gallager-Humblet-Spira protocol
+
+Usage: ghs [run] [options] <input-file>
+       ghs <command> [options] [arguments]
+
+Commands:
+  run       Compute the minimum spanning tree of a graph (the default)
+  verify    Compute the tree and check it against a sequential Kruskal
+  stats     Compute the tree and print message statistics
+  generate  Write a connected graph with unique weights
+  replay    Re-execute a trace recorded with --trace
+  help      Print this help, or the help of a command
+
+An <input-file> of '-' is read from stdin.
+Run 'ghs help <command>' for the options of a command.
+";
+
+const ENGINE_OPTIONS: &str = "\
+Input and protocol options:
+  --format <fmt>      Input format: tuple, dimacs, edges or mtx (default: from the file)
+  --weights <type>    Weight type: i32 (default), i64, u32, u64 or f64
+  --tie-break         Accept repeated weights, breaking ties by node indices
+  --backend <name>    threads (default, one thread per node), pool (nodes shared by
+                      worker threads) or sim (single-threaded)
+  --threads <n>       Workers of the pool backend (default: one per CPU); implies
+                      '--backend pool'
+  --seed <n>          Seed of the sim backend's scheduler (default 0); implies
+                      '--backend sim'
+  --timeout <secs>    Give up when the run takes longer; exits with status 2
+  --trace <file>      Record every node event as JSON Lines
+  -h, --help          Print this help
+";
+
+const RUN_HELP: &str = "\
+Usage: ghs [run] [options] <input-file>
+
+Computes the minimum spanning tree (or forest) of the input graph and prints its edges.
+
+Output options:
+  --output <fmt>      tuple (default), json, csv or dot
+  -o <file>           Write the tree to <file> instead of stdout
+  --verify            Also check the tree against Kruskal; report on stderr
+  --stats[=text|json] Print message statistics to stderr
+  --dot <file>        Write the graph as Graphviz DOT with the tree highlighted
+  --dot-fragments     Colour the nodes of --dot by fragment
+  --snapshots <dir>   Write a JSON and DOT snapshot after every merge and absorb
+";
+
+const VERIFY_HELP: &str = "\
+Usage: ghs verify [options] <input-file>
+
+Computes the minimum spanning tree and compares it with a sequential Kruskal.
+Prints both total weights and every differing edge; exits with status 1 when
+the edge sets differ.
+";
+
+const STATS_HELP: &str = "\
+Usage: ghs stats [options] <input-file>
+
+Computes the minimum spanning tree and prints per-type message counts, the
+number of deferred messages, the highest level reached and the elapsed time.
+
+Output options:
+  --json              Print the statistics as a JSON object
+";
+
+const GENERATE_HELP: &str = "\
+Usage: ghs generate [options]
+
+Writes a connected graph with unique weights in the (u, v, w) format.
+
+Options:
+  --family <name>     Shape of the graph (default tree-chords):
+                        erdos-renyi (er)      random pairs
+                        geometric             near points of the unit square
+                        grid, torus           2-D lattice, without or with wrap-around
+                        complete, ring, star
+                        tree-chords (tree)    random spanning tree plus random chords
+                        barabasi-albert (ba)  preferential attachment
+  --nodes <n>         Number of nodes (default 10)
+  --degree <d>        Average degree of the random families (default 4)
+  --edges <m>         Exact number of edges of tree-chords, from n-1 to n(n-1)/2
+  --seed <n>          Seed; the same seed gives the same graph (default 0)
+  -o <file>           Write the graph to <file> instead of stdout
+  -h, --help          Print this help
+";
+
+const REPLAY_HELP: &str = "\
+Usage: ghs replay [--step] <trace-file>
+
+Re-executes a trace recorded with --trace, checking that every node sends the
+recorded messages, and lists the nodes that did not halt. A <trace-file> of '-'
+is read from stdin.
+
+Options:
+  --step              Step through the trace interactively, reading commands
+                      from stdin; needs a <trace-file> other than '-'
+  -h, --help          Print this help
+";
+
+/// What the command line asks for.
+pub enum Command {
+    Run(RunArgs),
+    Generate(GenerateArgs),
+    Replay(ReplayArgs),
+    /// Print this text and exit successfully.
+    Help(String),
+}
+
+/// Which of the commands that run the protocol was given.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Mode {
+    Run,
+    Verify,
+    Stats,
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum WeightType {
+    I32,
+    I64,
+    U32,
+    U64,
+    F64,
+}
+
+impl FromStr for WeightType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "i32" => Ok(WeightType::I32),
+            "i64" => Ok(WeightType::I64),
+            "u32" => Ok(WeightType::U32),
+            "u64" => Ok(WeightType::U64),
+            "f64" => Ok(WeightType::F64),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum StatsFormat {
+    Text,
+    Json,
+}
+
+/// Where `--dot` writes the graph, and whether nodes are coloured by fragment.
+pub struct DotOutput {
+    pub file: String,
+    pub color_fragments: bool,
+}
+
+/// Arguments of `run`, `verify` and `stats`.
+pub struct RunArgs {
+    pub mode: Mode,
+    /// Path of the input, `-` for stdin.
+    pub input_file: String,
+    /// Input format; detected from the file when `None`.
+    pub format: Option<Format>,
+    pub weights: WeightType,
+    pub tie_break: bool,
+    pub backend: Backend,
+    pub timeout: Option<Duration>,
+    pub trace_file: Option<String>,
+    pub output: OutputFormat,
+    /// Where the result goes instead of stdout.
+    pub output_file: Option<String>,
+    pub verify: bool,
+    pub stats: Option<StatsFormat>,
+    pub dot: Option<DotOutput>,
+    pub snapshots_dir: Option<String>,
+}
+
+pub struct GenerateArgs {
+    pub family: Family,
+    pub nodes: u32,
+    pub degree: f64,
+    /// Exact edge count, which overrides `degree` for `Family::TreeChords`.
+    pub edges: Option<usize>,
+    pub seed: u64,
+    pub output_file: Option<String>,
+}
+
+pub struct ReplayArgs {
+    pub trace_file: String,
+    pub step: bool,
+}
+
+/// Parses the arguments that follow the program name.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut args: Vec<String> = args.into_iter().collect();
+    let command = match args.first().map(String::as_str) {
+        Some("run") => Some(Mode::Run),
+        Some("verify") => Some(Mode::Verify),
+        Some("stats") => Some(Mode::Stats),
+        Some("generate") => return parse_generate(&args[1..]),
+        Some("replay") => return parse_replay(&args[1..]),
+        Some("-h" | "--help") => return Ok(Command::Help(HELP.to_string())),
+        Some("help") => {
+            return match args.get(1).map(String::as_str) {
+                None => Ok(Command::Help(HELP.to_string())),
+                Some(command) => help(command).map(Command::Help),
+            };
+        }
+        _ => None,
+    };
+    if command.is_some() {
+        args.remove(0);
+    }
+    parse_run(command.unwrap_or(Mode::Run), &args)
+}
+
+fn help(command: &str) -> Result<String, String> {
+    match command {
+        "run" => Ok(format!("{}\n{}", RUN_HELP, ENGINE_OPTIONS)),
+        "verify" => Ok(format!("{}\n{}", VERIFY_HELP, ENGINE_OPTIONS)),
+        "stats" => Ok(format!("{}\n{}", STATS_HELP, ENGINE_OPTIONS)),
+        "generate" => Ok(GENERATE_HELP.to_string()),
+        "replay" => Ok(REPLAY_HELP.to_string()),
+        _ => Err(format!("unknown command '{}'", command)),
+    }
+}
+
+/// Walks the arguments, splitting `--name=value` and handing out option values.
+struct Args<'a> {
+    args: std::slice::Iter<'a, String>,
+    /// Value given inline with `--name=value`, not consumed yet.
+    inline: Option<&'a str>,
+}
+
+impl<'a> Args<'a> {
+    fn new(args: &'a [String]) -> Self {
+        Args {
+            args: args.iter(),
+            inline: None,
+        }
+    }
+
+    /// Next option name or operand.
+    fn next(&mut self) -> Result<Option<&'a str>, String> {
+        if let Some(value) = self.inline.take() {
+            return Err(format!("unexpected value '{}'", value));
+        }
+        Ok(self.args.next().map(|arg| match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => {
+                self.inline = Some(value);
+                name
+            }
+            _ => arg.as_str(),
+        }))
+    }
+
+    /// Value of the option `name` just returned by `next`.
+    fn value(&mut self, name: &str) -> Result<&'a str, String> {
+        match self.inline.take() {
+            Some(value) => Ok(value),
+            None => self
+                .args
+                .next()
+                .map(String::as_str)
+                .ok_or_else(|| format!("option '{}' needs a value", name)),
+        }
+    }
+
+    /// Value of `name`, parsed with `FromStr`.
+    fn parsed<T: FromStr>(&mut self, name: &str) -> Result<T, String> {
+        let value = self.value(name)?;
+        value
+            .parse()
+            .map_err(|_| format!("invalid value '{}' for '{}'", value, name))
+    }
+
+    /// Inline value of a flag whose value is optional, as in `--stats=json`.
+    fn optional_value(&mut self) -> Option<&'a str> {
+        self.inline.take()
+    }
+}
+
+fn parse_run(mode: Mode, args: &[String]) -> Result<Command, String> {
+    let mut run = RunArgs {
+        mode,
+        input_file: String::new(),
+        format: None,
+        weights: WeightType::I32,
+        tie_break: false,
+        backend: Backend::Threads,
+        timeout: None,
+        trace_file: None,
+        output: OutputFormat::Tuple,
+        output_file: None,
+        verify: false,
+        stats: None,
+        dot: None,
+        snapshots_dir: None,
+    };
+    let mut input_file = None;
+    let mut backend = None;
+    let mut seed = None;
+    let mut threads = None;
+    let mut dot_fragments = false;
+    let mut args = Args::new(args);
+    while let Some(arg) = args.next()? {
+        let command_name = match mode {
+            Mode::Run => "run",
+            Mode::Verify => "verify",
+            Mode::Stats => "stats",
+        };
+        match (arg, mode) {
+            ("-h" | "--help", _) => return help(command_name).map(Command::Help),
+            ("--format", _) => {
+                run.format = Some(
+                    args.parsed("--format")
+                        .map_err(|err| format!("{} (expected tuple, dimacs, edges or mtx)", err))?,
+                )
+            }
+            ("--weights", _) => {
+                run.weights = args
+                    .parsed("--weights")
+                    .map_err(|err| format!("{} (expected i32, i64, u32, u64 or f64)", err))?
+            }
+            ("--tie-break", _) => run.tie_break = true,
+            ("--backend", _) => {
+                backend = match args.value("--backend")? {
+                    name @ ("threads" | "pool" | "sim") => Some(name),
+                    other => {
+                        return Err(format!(
+                            "invalid value '{}' for '--backend' (expected threads, pool or sim)",
+                            other
+                        ))
+                    }
+                }
+            }
+            ("--seed", _) => seed = Some(args.parsed("--seed")?),
+            ("--threads", _) => {
+                threads = match args.parsed("--threads")? {
+                    0 => return Err(String::from("'--threads' must be at least 1")),
+                    threads => Some(threads),
+                }
+            }
+            ("--timeout", _) => {
+                let secs: f64 = args.parsed("--timeout")?;
+                if !(secs > 0.0 && secs.is_finite()) {
+                    return Err(format!("invalid value '{}' for '--timeout'", secs));
+                }
+                run.timeout = Some(Duration::from_secs_f64(secs));
+            }
+            ("--trace", _) => run.trace_file = Some(args.value("--trace")?.to_string()),
+            ("--json", Mode::Stats) => run.stats = Some(StatsFormat::Json),
+            ("--output", Mode::Run) => {
+                run.output = args
+                    .parsed("--output")
+                    .map_err(|err| format!("{} (expected tuple, json, csv or dot)", err))?
+            }
+            ("-o", Mode::Run) => run.output_file = Some(args.value("-o")?.to_string()),
+            ("--verify", Mode::Run) => run.verify = true,
+            ("--stats", Mode::Run) => {
+                run.stats = match args.optional_value() {
+                    None | Some("text") => Some(StatsFormat::Text),
+                    Some("json") => Some(StatsFormat::Json),
+                    Some(other) => {
+                        return Err(format!(
+                            "invalid value '{}' for '--stats' (expected text or json)",
+                            other
+                        ))
+                    }
+                }
+            }
+            ("--dot", Mode::Run) => {
+                run.dot = Some(DotOutput {
+                    file: args.value("--dot")?.to_string(),
+                    color_fragments: false,
+                })
+            }
+            ("--dot-fragments", Mode::Run) => dot_fragments = true,
+            ("--snapshots", Mode::Run) => {
+                run.snapshots_dir = Some(args.value("--snapshots")?.to_string())
+            }
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option '{}' for '{}'", arg, command_name))
+            }
+            _ if input_file.is_none() => input_file = Some(arg.to_string()),
+            _ => return Err(format!("unexpected argument '{}'", arg)),
+        }
+    }
+    run.input_file = input_file.ok_or("missing input file")?;
+    run.backend = match (backend, threads, seed) {
+        (_, Some(_), Some(_)) => {
+            return Err(String::from(
+                "'--threads' and '--seed' need different backends",
+            ));
+        }
+        (Some("sim") | None, None, Some(seed)) => Backend::Simulated { seed },
+        (Some("sim"), None, None) => Backend::Simulated { seed: 0 },
+        (Some("pool") | None, Some(threads), None) => Backend::Pool { threads },
+        (Some("pool"), None, None) => Backend::Pool {
+            threads: thread::available_parallelism().map_or(1, usize::from),
+        },
+        (Some(_), Some(_), None) => {
+            return Err(String::from("'--threads' needs the pool backend"));
+        }
+        (Some(_), None, Some(_)) => {
+            return Err(String::from("'--seed' needs the sim backend"));
+        }
+        _ => Backend::Threads,
+    };
+    match (&mut run.dot, dot_fragments) {
+        (Some(dot), true) => dot.color_fragments = true,
+        (None, true) => return Err(String::from("'--dot-fragments' needs '--dot <file>'")),
+        _ => {}
+    }
+    if mode == Mode::Stats && run.stats.is_none() {
+        run.stats = Some(StatsFormat::Text);
+    }
+    Ok(Command::Run(run))
+}
+
+fn parse_generate(args: &[String]) -> Result<Command, String> {
+    let mut generate = GenerateArgs {
+        family: Family::TreeChords,
+        nodes: 10,
+        degree: 4.0,
+        edges: None,
+        seed: 0,
+        output_file: None,
+    };
+    let mut args = Args::new(args);
+    while let Some(arg) = args.next()? {
+        match arg {
+            "-h" | "--help" => return help("generate").map(Command::Help),
+            "--family" => {
+                generate.family = args.parsed("--family").map_err(|err| {
+                    format!(
+                        "{} (expected erdos-renyi, geometric, grid, torus, complete, ring, \
+                         star, tree-chords or barabasi-albert)",
+                        err
+                    )
+                })?
+            }
+            "--nodes" => generate.nodes = args.parsed("--nodes")?,
+            "--degree" => {
+                generate.degree = args.parsed("--degree")?;
+                if !(generate.degree >= 0.0 && generate.degree.is_finite()) {
+                    return Err(format!(
+                        "invalid value '{}' for '--degree'",
+                        generate.degree
+                    ));
+                }
+            }
+            "--edges" => generate.edges = Some(args.parsed("--edges")?),
+            "--seed" => generate.seed = args.parsed("--seed")?,
+            "-o" => generate.output_file = Some(args.value("-o")?.to_string()),
+            _ if arg.starts_with('-') => {
+                return Err(format!("unknown option '{}' for 'generate'", arg))
+            }
+            _ => return Err(format!("unexpected argument '{}'", arg)),
+        }
+    }
+    if generate.edges.is_some() && generate.family != Family::TreeChords {
+        return Err(format!(
+            "'--edges' only applies to tree-chords, not {}",
+            generate.family
+        ));
+    }
+    Ok(Command::Generate(generate))
+}
+
+fn parse_replay(args: &[String]) -> Result<Command, String> {
+    let mut step = false;
+    let mut trace_file = None;
+    let mut args = Args::new(args);
+    while let Some(arg) = args.next()? {
+        match arg {
+            "-h" | "--help" => return help("replay").map(Command::Help),
+            "--step" => step = true,
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option '{}' for 'replay'", arg))
+            }
+            _ if trace_file.is_none() => trace_file = Some(arg.to_string()),
+            _ => return Err(format!("unexpected argument '{}'", arg)),
+        }
+    }
+    let trace_file = trace_file.ok_or("missing trace file")?;
+    if step && trace_file == "-" {
+        /* The step commands are read from stdin too */
+        return Err(String::from("'--step' cannot read the trace from stdin"));
+    }
+    Ok(Command::Replay(ReplayArgs { trace_file, step }))
+}
@@ -0,0 +1,79 @@
+use crate::node::{EdgeKey, NodeSnapshot, Status};
+use crate::weight::Weight;
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use petgraph::Undirected;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Renders `graph` in Graphviz DOT, styled by the edge statuses in `nodes`.
+///
+/// Edges are labelled with their weight. `Status::Branch` edges (the spanning
+/// forest) are drawn bold and red, `Status::Reject` edges dashed and grey, and
+/// edges still `Basic` in the default style. With `color_fragments`, each node
+/// is filled with a colour per fragment name and labelled with its level.
+pub fn to_dot<N, W: Weight>(
+    graph: &Graph<N, W, Undirected>,
+    nodes: &HashMap<NodeIndex, NodeSnapshot<W>>,
+    color_fragments: bool,
+) -> String {
+    let mut dot = String::from("graph ghs {\n    node [shape=circle];\n");
+
+    if color_fragments {
+        /* Number the fragment names in ascending order, one colour each */
+        let mut names: Vec<EdgeKey<W>> = nodes.values().filter_map(|node| node.name).collect();
+        names.sort();
+        names.dedup();
+        for node_index in graph.node_indices() {
+            let node = nodes.get(&node_index);
+            let color = node
+                .and_then(|node| node.name)
+                .and_then(|name| names.binary_search(&name).ok())
+                .map_or(String::from("white"), |id| {
+                    format!("\"/set312/{}\"", id % 12 + 1)
+                });
+            let level = node.map_or(0, |node| node.level);
+            writeln!(
+                dot,
+                "    {0} [label=\"{0}\\nL{1}\", style=filled, fillcolor={2}];",
+                node_index.index(),
+                level,
+                color
+            )
+            .expect("Error while writing to a String:");
+        }
+    } else {
+        for node_index in graph.node_indices() {
+            writeln!(dot, "    {};", node_index.index()).expect("Error while writing to a String:");
+        }
+    }
+
+    let status = |from: NodeIndex, to: NodeIndex| {
+        nodes
+            .get(&from)
+            .and_then(|node| node.status.get(&to))
+            .copied()
+    };
+    for edge in graph.edge_references() {
+        let (source, target) = (edge.source(), edge.target());
+        let statuses = [status(source, target), status(target, source)];
+        let style = if statuses.contains(&Some(Status::Branch)) {
+            ", style=bold, color=red, penwidth=2"
+        } else if statuses.contains(&Some(Status::Reject)) {
+            ", style=dashed, color=gray"
+        } else {
+            ""
+        };
+        writeln!(
+            dot,
+            "    {} -- {} [label=\"{}\"{}];",
+            source.index(),
+            target.index(),
+            edge.weight(),
+            style
+        )
+        .expect("Error while writing to a String:");
+    }
+    dot.push_str("}\n");
+    dot
+}
@@ -0,0 +1,449 @@
+use crate::rng::Rng;
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::Undirected;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// A generated graph: the node count and `(u, v, w)` edges with distinct weights.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Generated {
+    pub nodes: u32,
+    pub edges: Vec<(u32, u32, i32)>,
+}
+
+impl Generated {
+    /// Renders the graph in the `(u, v, w)` tuple format `parse_graph` reads.
+    pub fn to_tuple_format(&self) -> String {
+        let mut out = format!("{}\n", self.nodes);
+        for (u, v, w) in &self.edges {
+            writeln!(out, "({}, {}, {})", u, v, w).expect("Error while writing to a String:");
+        }
+        out
+    }
+
+    pub fn to_graph(&self) -> Graph<i32, i32, Undirected> {
+        let mut graph: Graph<i32, i32, Undirected> = Graph::default();
+        for _ in 0..self.nodes {
+            graph.add_node(i32::default());
+        }
+        for &(u, v, w) in &self.edges {
+            graph.add_edge(NodeIndex::new(u as usize), NodeIndex::new(v as usize), w);
+        }
+        graph
+    }
+}
+
+/// Why no graph with the requested parameters exists.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum GenerateError {
+    /// A connected graph on `nodes` nodes needs at least `nodes - 1` edges.
+    TooFewEdges { nodes: u32, edges: usize },
+    /// A simple graph on `nodes` nodes has at most `max` edges.
+    TooManyEdges {
+        nodes: u32,
+        edges: usize,
+        max: usize,
+    },
+}
+
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GenerateError::TooFewEdges { nodes, edges } => {
+                write!(f, "{} edges cannot connect {} nodes", edges, nodes)
+            }
+            GenerateError::TooManyEdges { nodes, edges, max } => write!(
+                f,
+                "{} nodes have room for {} edges, not {}",
+                nodes, max, edges
+            ),
+        }
+    }
+}
+
+impl Error for GenerateError {}
+
+/// Shape of a generated graph.
+///
+/// The random families take a target average degree; the others have a fixed
+/// shape and ignore it.
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub enum Family {
+    /// Every pair is an edge with probability `degree / (n - 1)`.
+    ErdosRenyi,
+    /// Random points in the unit square, joined when closer than the radius
+    /// that yields the average degree. Shorter edges get smaller weights.
+    Geometric,
+    /// Rows of `ceil(n / floor(sqrt(n)))` nodes, each node joined to its right
+    /// and lower neighbours; the last row may be partial.
+    Grid,
+    /// A grid whose rows and columns wrap around. The row count is the largest
+    /// divisor of `n` not above `sqrt(n)`, so a prime `n` gives a ring.
+    Torus,
+    /// Every pair of nodes.
+    Complete,
+    /// Node `i` joined to node `i + 1`, and the last node to node 0.
+    Ring,
+    /// Node 0 joined to every other node.
+    Star,
+    /// A random spanning tree plus random chords, `n * degree / 2` edges in total.
+    #[default]
+    TreeChords,
+    /// Preferential attachment: each new node joins `degree / 2` earlier nodes
+    /// picked with probability proportional to their degree.
+    BarabasiAlbert,
+}
+
+impl fmt::Display for Family {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Family::ErdosRenyi => write!(f, "erdos-renyi"),
+            Family::Geometric => write!(f, "geometric"),
+            Family::Grid => write!(f, "grid"),
+            Family::Torus => write!(f, "torus"),
+            Family::Complete => write!(f, "complete"),
+            Family::Ring => write!(f, "ring"),
+            Family::Star => write!(f, "star"),
+            Family::TreeChords => write!(f, "tree-chords"),
+            Family::BarabasiAlbert => write!(f, "barabasi-albert"),
+        }
+    }
+}
+
+impl FromStr for Family {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "erdos-renyi" | "er" => Ok(Family::ErdosRenyi),
+            "geometric" => Ok(Family::Geometric),
+            "grid" => Ok(Family::Grid),
+            "torus" => Ok(Family::Torus),
+            "complete" => Ok(Family::Complete),
+            "ring" => Ok(Family::Ring),
+            "star" => Ok(Family::Star),
+            "tree-chords" | "tree" => Ok(Family::TreeChords),
+            "barabasi-albert" | "ba" => Ok(Family::BarabasiAlbert),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Connected graph of `family` with `nodes` nodes and unique weights.
+///
+/// `degree` is the target average degree of the random families; a negative
+/// or NaN `degree` counts as 0, which leaves only what keeps the graph
+/// connected. Where a family may come out disconnected, consecutive components
+/// are joined by an edge between random members. The weights are a
+/// permutation of `1..=edges`, random except for `Family::Geometric`. The same
+/// seed always yields the same graph.
+pub fn generate(family: Family, nodes: u32, degree: f64, seed: u64) -> Generated {
+    let n = nodes as usize;
+    let degree = if degree > 0.0 { degree } else { 0.0 };
+    let mut rng = Rng::new(seed);
+    let mut pairs = Pairs::new(nodes);
+    match family {
+        Family::ErdosRenyi => {
+            erdos_renyi(&mut pairs, degree / (n.max(2) - 1) as f64, &mut rng);
+            pairs.connect(&mut rng);
+        }
+        Family::Geometric => {
+            let points: Vec<(f64, f64)> = (0..n).map(|_| (rng.uniform(), rng.uniform())).collect();
+            /* A disc of radius r holds n * pi * r^2 of the n points on average */
+            let radius = (degree / (std::f64::consts::PI * n.max(1) as f64)).sqrt();
+            geometric(&mut pairs, &points, radius);
+            pairs.connect(&mut rng);
+            return pairs.into_generated_by_length(&points);
+        }
+        Family::Grid => {
+            let rows = grid_rows(n);
+            let cols = n.div_ceil(rows);
+            for i in 0..n {
+                if (i + 1) % cols != 0 && i + 1 < n {
+                    pairs.add(i, i + 1);
+                }
+                if i + cols < n {
+                    pairs.add(i, i + cols);
+                }
+            }
+        }
+        Family::Torus => {
+            let rows = (1..=grid_rows(n))
+                .rev()
+                .find(|&rows| n.is_multiple_of(rows))
+                .unwrap_or(1);
+            let cols = n / rows;
+            for i in 0..n {
+                let (row, col) = (i / cols, i % cols);
+                pairs.add(i, row * cols + (col + 1) % cols);
+                pairs.add(i, (row + 1) % rows * cols + col);
+            }
+        }
+        Family::Complete => {
+            for u in 0..n {
+                for v in u + 1..n {
+                    pairs.add(u, v);
+                }
+            }
+        }
+        Family::Ring => {
+            for i in 0..n {
+                pairs.add(i, (i + 1) % n);
+            }
+        }
+        Family::Star => {
+            for i in 1..n {
+                pairs.add(0, i);
+            }
+        }
+        Family::TreeChords => {
+            let max = n * n.saturating_sub(1) / 2;
+            let edges =
+                ((n as f64 * degree / 2.0).round() as usize).clamp(n.saturating_sub(1), max);
+            return random_connected(nodes, edges, seed).expect("edge count within bounds");
+        }
+        Family::BarabasiAlbert => {
+            /* A new node cannot attach to more nodes than there are before it */
+            let m = ((degree / 2.0).round() as usize).clamp(1, n.saturating_sub(1).max(1));
+            barabasi_albert(&mut pairs, m, &mut rng);
+        }
+    }
+    pairs.into_generated(&mut rng)
+}
+
+/// Random connected graph with `nodes` nodes and exactly `edges` edges.
+///
+/// A random spanning tree keeps the graph connected; the remaining edges join
+/// random pairs of nodes that are not adjacent yet. The weights are a random
+/// permutation of `1..=edges`, so they are unique. The same seed always yields
+/// the same graph.
+pub fn random_connected(nodes: u32, edges: usize, seed: u64) -> Result<Generated, GenerateError> {
+    let n = nodes as usize;
+    let max = n * n.saturating_sub(1) / 2;
+    if edges + 1 < n {
+        return Err(GenerateError::TooFewEdges { nodes, edges });
+    }
+    if edges > max {
+        return Err(GenerateError::TooManyEdges { nodes, edges, max });
+    }
+    let mut rng = Rng::new(seed);
+    let mut pairs = Pairs::new(nodes);
+
+    let mut order: Vec<usize> = (0..n).collect();
+    rng.shuffle(&mut order);
+    for i in 1..n {
+        pairs.add(order[i], order[rng.below(i)]);
+    }
+
+    let chords = edges - pairs.len();
+    if chords > (max - pairs.len()) / 2 {
+        /* Dense: pick from the list of every missing pair */
+        let mut missing: Vec<(usize, usize)> = (0..n)
+            .flat_map(|u| (u + 1..n).map(move |v| (u, v)))
+            .filter(|&(u, v)| !pairs.contains(u, v))
+            .collect();
+        rng.shuffle(&mut missing);
+        for (u, v) in missing.into_iter().take(chords) {
+            pairs.add(u, v);
+        }
+    } else {
+        while pairs.len() < edges {
+            pairs.add(rng.below(n), rng.below(n));
+        }
+    }
+    Ok(pairs.into_generated(&mut rng))
+}
+
+/// Number of rows of a grid of `n` nodes.
+fn grid_rows(n: usize) -> usize {
+    ((n as f64).sqrt() as usize).max(1)
+}
+
+/// G(n, p) in time linear in the number of edges, by skipping geometrically
+/// distributed runs of absent pairs (Batagelj and Brandes, 2005).
+fn erdos_renyi(pairs: &mut Pairs, p: f64, rng: &mut Rng) {
+    let n = pairs.nodes as usize;
+    if p >= 1.0 {
+        for u in 0..n {
+            for v in u + 1..n {
+                pairs.add(u, v);
+            }
+        }
+        return;
+    }
+    if p <= 0.0 {
+        return;
+    }
+    let log_q = (1.0 - p).ln();
+    let (mut v, mut w) = (1usize, -1i64);
+    while v < n {
+        let skip = ((1.0 - rng.uniform()).ln() / log_q).floor();
+        w = w.saturating_add(1).saturating_add(skip as i64);
+        while v < n && w >= v as i64 {
+            w -= v as i64;
+            v += 1;
+        }
+        if v < n {
+            pairs.add(v, w as usize);
+        }
+    }
+}
+
+/// Joins the points closer than `radius`, looking only at neighbouring cells
+/// of a grid whose cells are at least `radius` wide.
+fn geometric(pairs: &mut Pairs, points: &[(f64, f64)], radius: f64) {
+    let cells = if radius > 0.0 {
+        ((1.0 / radius) as usize).clamp(1, grid_rows(points.len()))
+    } else {
+        1
+    };
+    let cell_of = |x: f64| ((x * cells as f64) as usize).min(cells - 1);
+    let mut grid: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    for (i, &(x, y)) in points.iter().enumerate() {
+        grid.entry((cell_of(x), cell_of(y))).or_default().push(i);
+    }
+    for (u, &(x, y)) in points.iter().enumerate() {
+        let (cx, cy) = (cell_of(x), cell_of(y));
+        for nx in cx.saturating_sub(1)..=(cx + 1).min(cells - 1) {
+            for ny in cy.saturating_sub(1)..=(cy + 1).min(cells - 1) {
+                for &v in grid.get(&(nx, ny)).into_iter().flatten() {
+                    let (dx, dy) = (points[v].0 - x, points[v].1 - y);
+                    if u < v && dx * dx + dy * dy < radius * radius {
+                        pairs.add(u, v);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Starts from a complete graph on `m + 1` nodes; every further node joins `m`
+/// distinct earlier nodes drawn with probability proportional to their degree.
+fn barabasi_albert(pairs: &mut Pairs, m: usize, rng: &mut Rng) {
+    let n = pairs.nodes as usize;
+    let initial = (m + 1).min(n);
+    /* Every node appears once per incident edge */
+    let mut endpoints = vec![];
+    for u in 0..initial {
+        for v in u + 1..initial {
+            pairs.add(u, v);
+            endpoints.extend([u, v]);
+        }
+    }
+    for u in initial..n {
+        let mut targets = HashSet::new();
+        while targets.len() < m {
+            targets.insert(endpoints[rng.below(endpoints.len())]);
+        }
+        let mut targets: Vec<usize> = targets.into_iter().collect();
+        targets.sort_unstable();
+        for v in targets {
+            pairs.add(u, v);
+            endpoints.extend([u, v]);
+        }
+    }
+}
+
+/// Edges of a graph under construction, without self-loops or parallel edges.
+struct Pairs {
+    nodes: u32,
+    pairs: Vec<(u32, u32)>,
+    present: HashSet<(u32, u32)>,
+}
+
+impl Pairs {
+    fn new(nodes: u32) -> Self {
+        Pairs {
+            nodes,
+            pairs: vec![],
+            present: HashSet::new(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    fn contains(&self, u: usize, v: usize) -> bool {
+        self.present.contains(&Pairs::key(u, v))
+    }
+
+    /// Adds the edge `u - v` unless it is a self-loop or already present.
+    fn add(&mut self, u: usize, v: usize) {
+        if u != v && self.present.insert(Pairs::key(u, v)) {
+            self.pairs.push(Pairs::key(u, v));
+        }
+    }
+
+    fn key(u: usize, v: usize) -> (u32, u32) {
+        (u.min(v) as u32, u.max(v) as u32)
+    }
+
+    /// Joins each component to the next by an edge between random members.
+    fn connect(&mut self, rng: &mut Rng) {
+        fn find(parent: &mut [usize], mut u: usize) -> usize {
+            while parent[u] != u {
+                parent[u] = parent[parent[u]];
+                u = parent[u];
+            }
+            u
+        }
+        let n = self.nodes as usize;
+        let mut parent: Vec<usize> = (0..n).collect();
+        for &(u, v) in &self.pairs {
+            let (ru, rv) = (find(&mut parent, u as usize), find(&mut parent, v as usize));
+            parent[ru] = rv;
+        }
+        let mut components: HashMap<usize, Vec<usize>> = HashMap::new();
+        for u in 0..n {
+            components.entry(find(&mut parent, u)).or_default().push(u);
+        }
+        let mut components: Vec<Vec<usize>> = components.into_values().collect();
+        components.sort_unstable();
+        for pair in components.windows(2) {
+            let u = pair[0][rng.below(pair[0].len())];
+            let v = pair[1][rng.below(pair[1].len())];
+            self.add(u, v);
+        }
+    }
+
+    /// Weighs the edges with a random permutation of `1..=edges`.
+    fn into_generated(mut self, rng: &mut Rng) -> Generated {
+        let mut weights: Vec<i32> = (1..=self.pairs.len() as i32).collect();
+        rng.shuffle(&mut weights);
+        self.pairs.sort_unstable();
+        Generated {
+            nodes: self.nodes,
+            edges: self
+                .pairs
+                .into_iter()
+                .zip(weights)
+                .map(|((u, v), w)| (u, v, w))
+                .collect(),
+        }
+    }
+
+    /// Weighs the edges with `1..=edges` in order of their length between `points`.
+    fn into_generated_by_length(mut self, points: &[(f64, f64)]) -> Generated {
+        let length = |&(u, v): &(u32, u32)| {
+            let ((x1, y1), (x2, y2)) = (points[u as usize], points[v as usize]);
+            (x1 - x2).hypot(y1 - y2)
+        };
+        self.pairs
+            .sort_unstable_by(|a, b| length(a).total_cmp(&length(b)).then(a.cmp(b)));
+        let mut edges: Vec<(u32, u32, i32)> = self
+            .pairs
+            .into_iter()
+            .zip(1..)
+            .map(|((u, v), w)| (u, v, w))
+            .collect();
+        edges.sort_unstable();
+        Generated {
+            nodes: self.nodes,
+            edges,
+        }
+    }
+}
@@ -0,0 +1,616 @@
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::Undirected;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Input file formats `parse_graph_as` can read.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Format {
+    /// The `(u, v, w)` tuple format described in the README.
+    Tuple,
+    /// DIMACS shortest-path `.gr` files: `p sp <nodes> <arcs>` and `a <u> <v> <w>` lines, 1-based.
+    Dimacs,
+    /// One `u v w` or `u,v,w` edge per line, 0-based, with an optional header line.
+    EdgeList,
+    /// Matrix Market `coordinate` matrices with `real` or `integer` entries, 1-based.
+    MatrixMarket,
+}
+
+impl Format {
+    /// Format implied by the extension of `path`, if any.
+    pub fn from_extension(path: &str) -> Option<Format> {
+        let extension = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "gr" | "dimacs" => Some(Format::Dimacs),
+            "csv" | "tsv" | "edges" | "el" => Some(Format::EdgeList),
+            "mtx" => Some(Format::MatrixMarket),
+            _ => None,
+        }
+    }
+
+    /// Picks the format from the extension of `path`, or else from the first
+    /// line of `text` that is not a `#` or `%` comment.
+    pub fn detect(path: &str, text: &str) -> Format {
+        if let Some(format) = Format::from_extension(path) {
+            return format;
+        }
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+        if lines
+            .clone()
+            .next()
+            .is_some_and(|line| line.starts_with("%%MatrixMarket"))
+        {
+            return Format::MatrixMarket;
+        }
+        let first = lines.find(|line| !line.starts_with('#') && !line.starts_with('%'));
+        match first {
+            Some(line) if line == "c" || line.starts_with("c ") || line.starts_with("p ") => {
+                Format::Dimacs
+            }
+            Some(line) if u32::from_str(strip_comment(line).trim()).is_ok() => Format::Tuple,
+            Some(_) => Format::EdgeList,
+            None => Format::Tuple,
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Format::Tuple => write!(f, "tuple"),
+            Format::Dimacs => write!(f, "dimacs"),
+            Format::EdgeList => write!(f, "edges"),
+            Format::MatrixMarket => write!(f, "mtx"),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tuple" => Ok(Format::Tuple),
+            "dimacs" | "gr" => Ok(Format::Dimacs),
+            "edges" | "csv" => Ok(Format::EdgeList),
+            "mtx" | "matrix-market" => Ok(Format::MatrixMarket),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Largest number of nodes an input may declare or imply.
+///
+/// The parsers allocate every node before reading the edges, so without a
+/// limit a single stray count or index would make them allocate that many.
+pub const MAX_NODES: u32 = 1 << 24;
+
+/// What went wrong while parsing an input file.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum ParseErrorKind {
+    /// The input has no node count line.
+    MissingNodeCount,
+    /// The first line is not a non-negative integer up to `MAX_NODES`.
+    InvalidNodeCount,
+    /// The line is not of the form `(u, v, w)`.
+    MalformedTuple,
+    /// A node index is not an integer.
+    InvalidNumber,
+    /// The weight cannot be parsed as the requested weight type.
+    InvalidWeight,
+    /// A node index is below zero.
+    NegativeIndex,
+    /// A node index is not below the declared node count (or, for 1-based
+    /// formats, not between 1 and the node count).
+    IndexOutOfRange { nodes: u32 },
+    /// The DIMACS problem line or Matrix Market banner or size line is missing.
+    MissingHeader,
+    /// The DIMACS problem line or Matrix Market banner or size line cannot be read.
+    InvalidHeader,
+    /// A Matrix Market file that is not a square `coordinate` matrix of
+    /// `real` or `integer` entries, stored `general` or `symmetric`.
+    UnsupportedMatrix,
+    /// A line of a DIMACS, edge list or Matrix Market file is not an edge.
+    MalformedEdge,
+    /// The header announces a different number of edges than the file holds.
+    EdgeCountMismatch { expected: usize, found: usize },
+}
+
+/// A parse failure, located by 1-based line and column.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: ", self.line, self.column)?;
+        match self.kind {
+            ParseErrorKind::MissingNodeCount => write!(f, "missing node count"),
+            ParseErrorKind::InvalidNodeCount => write!(f, "invalid node count '{}'", self.text),
+            ParseErrorKind::MalformedTuple => {
+                write!(f, "expected an edge '(u, v, w)', found '{}'", self.text)
+            }
+            ParseErrorKind::InvalidNumber => write!(f, "'{}' is not an integer", self.text),
+            ParseErrorKind::InvalidWeight => write!(f, "'{}' is not a valid weight", self.text),
+            ParseErrorKind::NegativeIndex => write!(f, "negative node index '{}'", self.text),
+            ParseErrorKind::IndexOutOfRange { nodes } => write!(
+                f,
+                "node index '{}' is out of range for {} nodes",
+                self.text, nodes
+            ),
+            ParseErrorKind::MissingHeader => write!(f, "missing header"),
+            ParseErrorKind::InvalidHeader => write!(f, "invalid header '{}'", self.text),
+            ParseErrorKind::UnsupportedMatrix => {
+                write!(f, "unsupported matrix type '{}'", self.text)
+            }
+            ParseErrorKind::MalformedEdge => write!(f, "expected an edge, found '{}'", self.text),
+            ParseErrorKind::EdgeCountMismatch { expected, found } => {
+                write!(f, "header announces {} edges, found {}", expected, found)
+            }
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// Parses `input_buffer` as `format`.
+pub fn parse_graph_as<W: FromStr + PartialEq + Clone>(
+    input_buffer: &str,
+    format: Format,
+) -> Result<Graph<i32, W, Undirected>, ParseError> {
+    match format {
+        Format::Tuple => parse_graph(input_buffer),
+        Format::Dimacs => parse_dimacs(input_buffer),
+        Format::EdgeList => parse_edge_list(input_buffer),
+        Format::MatrixMarket => parse_matrix_market(input_buffer),
+    }
+}
+
+/// Parses the `(u, v, w)` tuple format described in the README.
+///
+/// The first line is the number of nodes, at most `MAX_NODES`; every
+/// following line is an edge.
+/// Blank lines and `#` comments are ignored. Weights are parsed with `W::from_str`.
+pub fn parse_graph<W: FromStr>(
+    input_buffer: &str,
+) -> Result<Graph<i32, W, Undirected>, ParseError> {
+    let mut lines = input_buffer
+        .lines()
+        .enumerate()
+        .map(|(line_no, line)| (line_no + 1, strip_comment(line)))
+        .filter(|(_, line)| !line.trim().is_empty());
+
+    let (line_no, line) = lines.next().ok_or(ParseError {
+        line: input_buffer.lines().count().max(1),
+        column: 1,
+        text: String::new(),
+        kind: ParseErrorKind::MissingNodeCount,
+    })?;
+    let nodes = u32::from_str(line.trim())
+        .ok()
+        .filter(|&nodes| nodes <= MAX_NODES)
+        .ok_or_else(|| ParseError {
+            line: line_no,
+            column: column_of(line, line.trim()),
+            text: line.trim().to_string(),
+            kind: ParseErrorKind::InvalidNodeCount,
+        })?;
+
+    let mut graph: Graph<i32, W, Undirected> = Graph::default();
+    for _ in 0..nodes {
+        graph.add_node(i32::default());
+    }
+    for (line_no, line) in lines {
+        let (u, v, w) = parse_edge(line_no, line, nodes)?;
+        graph.add_edge(NodeIndex::new(u as usize), NodeIndex::new(v as usize), w);
+    }
+    Ok(graph)
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.find('#') {
+        Some(pos) => &line[..pos],
+        None => line,
+    }
+}
+
+/// 1-based column of `part`, which must be a subslice of `line`.
+fn column_of(line: &str, part: &str) -> usize {
+    part.as_ptr() as usize - line.as_ptr() as usize + 1
+}
+
+fn parse_edge<W: FromStr>(
+    line_no: usize,
+    line: &str,
+    nodes: u32,
+) -> Result<(u32, u32, W), ParseError> {
+    let trimmed = line.trim();
+    let error = |part: &str, kind| ParseError {
+        line: line_no,
+        column: column_of(line, part),
+        text: part.to_string(),
+        kind,
+    };
+    let inner = trimmed
+        .strip_prefix('(')
+        .and_then(|rest| rest.strip_suffix(')'))
+        .ok_or_else(|| error(trimmed, ParseErrorKind::MalformedTuple))?;
+    let fields: Vec<&str> = inner.split(',').map(str::trim).collect();
+    if fields.len() != 3 {
+        return Err(error(trimmed, ParseErrorKind::MalformedTuple));
+    }
+
+    let mut indices = [0; 2];
+    for (index, field) in indices.iter_mut().zip(&fields[..2]) {
+        let value =
+            i64::from_str(field).map_err(|_| error(field, ParseErrorKind::InvalidNumber))?;
+        if value < 0 {
+            return Err(error(field, ParseErrorKind::NegativeIndex));
+        }
+        if value >= nodes as i64 {
+            return Err(error(field, ParseErrorKind::IndexOutOfRange { nodes }));
+        }
+        *index = value as u32;
+    }
+    let weight =
+        W::from_str(fields[2]).map_err(|_| error(fields[2], ParseErrorKind::InvalidWeight))?;
+    Ok((indices[0], indices[1], weight))
+}
+
+/// Parses a DIMACS shortest-path (`.gr`) file.
+///
+/// `c` lines are comments, the `p sp <nodes> <arcs>` line (with at most
+/// `MAX_NODES` nodes) comes first and every `a <u> <v> <w>` line is an arc
+/// between 1-based nodes. Such files list each undirected edge as two
+/// opposite arcs of equal weight; those are merged into one edge, any other
+/// arcs are kept as they are.
+pub fn parse_dimacs<W: FromStr + PartialEq + Clone>(
+    input_buffer: &str,
+) -> Result<Graph<i32, W, Undirected>, ParseError> {
+    let mut lines = input_buffer
+        .lines()
+        .enumerate()
+        .map(|(line_no, line)| (line_no + 1, line))
+        .filter(|(_, line)| !line.trim().is_empty() && !is_dimacs_comment(line));
+
+    let (line_no, line) = lines.next().ok_or_else(|| missing_header(input_buffer))?;
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let (nodes, arcs) = match fields.as_slice() {
+        ["p", _, nodes, arcs] => (
+            u32::from_str(nodes)
+                .ok()
+                .filter(|&nodes| nodes <= MAX_NODES)
+                .ok_or_else(|| field_error(line_no, line, nodes, ParseErrorKind::InvalidHeader))?,
+            usize::from_str(arcs)
+                .map_err(|_| field_error(line_no, line, arcs, ParseErrorKind::InvalidHeader))?,
+        ),
+        _ => {
+            return Err(field_error(
+                line_no,
+                line,
+                line.trim(),
+                ParseErrorKind::InvalidHeader,
+            ));
+        }
+    };
+
+    let mut edges = UndirectedEdges::new(nodes);
+    let mut found = 0;
+    for (line_no, line) in lines {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            ["a", u, v, w] => {
+                let u = parse_one_based(line_no, line, u, nodes)?;
+                let v = parse_one_based(line_no, line, v, nodes)?;
+                let w = parse_weight(line_no, line, w)?;
+                edges.add_arc(u, v, w);
+                found += 1;
+            }
+            _ => {
+                return Err(field_error(
+                    line_no,
+                    line,
+                    line.trim(),
+                    ParseErrorKind::MalformedEdge,
+                ));
+            }
+        }
+    }
+    if found != arcs {
+        return Err(ParseError {
+            line: input_buffer.lines().count().max(1),
+            column: 1,
+            text: String::new(),
+            kind: ParseErrorKind::EdgeCountMismatch {
+                expected: arcs,
+                found,
+            },
+        });
+    }
+    Ok(edges.graph)
+}
+
+fn is_dimacs_comment(line: &str) -> bool {
+    let line = line.trim_start();
+    line == "c" || line.starts_with("c ") || line.starts_with("c\t")
+}
+
+/// Parses a plain edge list: one `u v w` edge per line, fields separated by
+/// whitespace or by commas.
+///
+/// Node indices are 0-based and the node count is one more than the largest
+/// index, which must stay below `MAX_NODES`. Lines starting with
+/// `#` or `%` are comments; a first line whose first field is not a number is
+/// taken as a header and skipped.
+pub fn parse_edge_list<W: FromStr>(
+    input_buffer: &str,
+) -> Result<Graph<i32, W, Undirected>, ParseError> {
+    let mut edges = vec![];
+    let mut nodes = 0;
+    let mut first = true;
+    for (line_no, line) in input_buffer.lines().enumerate() {
+        let line_no = line_no + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with('%') {
+            continue;
+        }
+        let fields: Vec<&str> = if trimmed.contains(',') {
+            trimmed.split(',').map(str::trim).collect()
+        } else {
+            trimmed.split_whitespace().collect()
+        };
+        let is_header = first
+            && fields
+                .first()
+                .is_some_and(|field| i64::from_str(field).is_err());
+        first = false;
+        if is_header {
+            continue;
+        }
+        if fields.len() != 3 {
+            return Err(field_error(
+                line_no,
+                line,
+                trimmed,
+                ParseErrorKind::MalformedEdge,
+            ));
+        }
+        let mut indices = [0; 2];
+        for (index, field) in indices.iter_mut().zip(&fields[..2]) {
+            let value = i64::from_str(field)
+                .map_err(|_| field_error(line_no, line, field, ParseErrorKind::InvalidNumber))?;
+            if value < 0 {
+                return Err(field_error(
+                    line_no,
+                    line,
+                    field,
+                    ParseErrorKind::NegativeIndex,
+                ));
+            }
+            if value >= i64::from(MAX_NODES) {
+                return Err(field_error(
+                    line_no,
+                    line,
+                    field,
+                    ParseErrorKind::IndexOutOfRange { nodes: MAX_NODES },
+                ));
+            }
+            *index = value as u32;
+            nodes = nodes.max(*index + 1);
+        }
+        let weight = parse_weight(line_no, line, fields[2])?;
+        edges.push((indices[0], indices[1], weight));
+    }
+
+    let mut graph: Graph<i32, W, Undirected> = Graph::default();
+    for _ in 0..nodes {
+        graph.add_node(i32::default());
+    }
+    for (u, v, w) in edges {
+        graph.add_edge(NodeIndex::new(u as usize), NodeIndex::new(v as usize), w);
+    }
+    Ok(graph)
+}
+
+/// Parses a Matrix Market `coordinate` file as a weighted adjacency matrix.
+///
+/// The banner must declare `real` or `integer` entries stored `general` or
+/// `symmetric`; the matrix must be square, with at most `MAX_NODES` rows. Rows
+/// and columns are 1-based node indices and every entry is an edge. For
+/// `general` matrices, entries `(i, j)` and `(j, i)` with equal values are
+/// merged into one edge.
+pub fn parse_matrix_market<W: FromStr + PartialEq + Clone>(
+    input_buffer: &str,
+) -> Result<Graph<i32, W, Undirected>, ParseError> {
+    let mut lines = input_buffer
+        .lines()
+        .enumerate()
+        .map(|(line_no, line)| (line_no + 1, line));
+
+    let (line_no, banner) = lines.next().ok_or_else(|| missing_header(input_buffer))?;
+    let fields: Vec<String> = banner
+        .split_whitespace()
+        .map(str::to_ascii_lowercase)
+        .collect();
+    let fields: Vec<&str> = fields.iter().map(String::as_str).collect();
+    let symmetric = match fields.as_slice() {
+        ["%%matrixmarket", "matrix", "coordinate", "real" | "integer", "general"] => false,
+        ["%%matrixmarket", "matrix", "coordinate", "real" | "integer", "symmetric"] => true,
+        ["%%matrixmarket", "matrix", ..] => {
+            return Err(field_error(
+                line_no,
+                banner,
+                banner.trim(),
+                ParseErrorKind::UnsupportedMatrix,
+            ));
+        }
+        _ => {
+            return Err(field_error(
+                line_no,
+                banner,
+                banner.trim(),
+                ParseErrorKind::InvalidHeader,
+            ));
+        }
+    };
+
+    let mut lines = lines.filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('%'));
+    let (line_no, line) = lines.next().ok_or_else(|| missing_header(input_buffer))?;
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let (nodes, entries) = match fields.as_slice() {
+        [rows, columns, entries] => {
+            let header =
+                |field: &&str| field_error(line_no, line, field, ParseErrorKind::InvalidHeader);
+            let rows = u32::from_str(rows)
+                .ok()
+                .filter(|&rows| rows <= MAX_NODES)
+                .ok_or_else(|| header(rows))?;
+            let columns = u32::from_str(columns).map_err(|_| header(columns))?;
+            if rows != columns {
+                return Err(field_error(
+                    line_no,
+                    line,
+                    line.trim(),
+                    ParseErrorKind::UnsupportedMatrix,
+                ));
+            }
+            (rows, usize::from_str(entries).map_err(|_| header(entries))?)
+        }
+        _ => {
+            return Err(field_error(
+                line_no,
+                line,
+                line.trim(),
+                ParseErrorKind::InvalidHeader,
+            ));
+        }
+    };
+
+    let mut edges = UndirectedEdges::new(nodes);
+    let mut found = 0;
+    for (line_no, line) in lines {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            [i, j, value] => {
+                let i = parse_one_based(line_no, line, i, nodes)?;
+                let j = parse_one_based(line_no, line, j, nodes)?;
+                let value = parse_weight(line_no, line, value)?;
+                if symmetric {
+                    edges.add_edge(i, j, value);
+                } else {
+                    edges.add_arc(i, j, value);
+                }
+                found += 1;
+            }
+            _ => {
+                return Err(field_error(
+                    line_no,
+                    line,
+                    line.trim(),
+                    ParseErrorKind::MalformedEdge,
+                ));
+            }
+        }
+    }
+    if found != entries {
+        return Err(ParseError {
+            line: input_buffer.lines().count().max(1),
+            column: 1,
+            text: String::new(),
+            kind: ParseErrorKind::EdgeCountMismatch {
+                expected: entries,
+                found,
+            },
+        });
+    }
+    Ok(edges.graph)
+}
+
+/// Builds an undirected graph from directed arcs, merging opposite arcs of equal weight.
+struct UndirectedEdges<W> {
+    graph: Graph<i32, W, Undirected>,
+    /// Arcs not matched by an opposite arc yet, by `(from, to)`.
+    unpaired: HashMap<(u32, u32), Vec<W>>,
+}
+
+impl<W: PartialEq + Clone> UndirectedEdges<W> {
+    fn new(nodes: u32) -> Self {
+        let mut graph: Graph<i32, W, Undirected> = Graph::default();
+        for _ in 0..nodes {
+            graph.add_node(i32::default());
+        }
+        UndirectedEdges {
+            graph,
+            unpaired: HashMap::new(),
+        }
+    }
+
+    fn add_edge(&mut self, u: u32, v: u32, w: W) {
+        self.graph
+            .add_edge(NodeIndex::new(u as usize), NodeIndex::new(v as usize), w);
+    }
+
+    /// Adds the arc `u -> v` as an edge, unless it is the reverse of an earlier unpaired arc.
+    fn add_arc(&mut self, u: u32, v: u32, w: W) {
+        if let Some(weights) = self.unpaired.get_mut(&(v, u)) {
+            if let Some(pos) = weights.iter().position(|weight| *weight == w) {
+                weights.swap_remove(pos);
+                return;
+            }
+        }
+        self.unpaired.entry((u, v)).or_default().push(w.clone());
+        self.add_edge(u, v, w);
+    }
+}
+
+fn missing_header(input_buffer: &str) -> ParseError {
+    ParseError {
+        line: input_buffer.lines().count().max(1),
+        column: 1,
+        text: String::new(),
+        kind: ParseErrorKind::MissingHeader,
+    }
+}
+
+fn field_error(line_no: usize, line: &str, part: &str, kind: ParseErrorKind) -> ParseError {
+    ParseError {
+        line: line_no,
+        column: column_of(line, part),
+        text: part.to_string(),
+        kind,
+    }
+}
+
+/// Parses a 1-based node index between 1 and `nodes` into a 0-based one.
+fn parse_one_based(line_no: usize, line: &str, field: &str, nodes: u32) -> Result<u32, ParseError> {
+    let value = i64::from_str(field)
+        .map_err(|_| field_error(line_no, line, field, ParseErrorKind::InvalidNumber))?;
+    if value < 0 {
+        return Err(field_error(
+            line_no,
+            line,
+            field,
+            ParseErrorKind::NegativeIndex,
+        ));
+    }
+    if value == 0 || value > nodes as i64 {
+        return Err(field_error(
+            line_no,
+            line,
+            field,
+            ParseErrorKind::IndexOutOfRange { nodes },
+        ));
+    }
+    Ok(value as u32 - 1)
+}
+
+fn parse_weight<W: FromStr>(line_no: usize, line: &str, field: &str) -> Result<W, ParseError> {
+    W::from_str(field).map_err(|_| field_error(line_no, line, field, ParseErrorKind::InvalidWeight))
+}
@@ -0,0 +1,184 @@
+//! GHS (Gallager-Humblet-Spira) distributed minimum spanning tree.
+//!
+//! `run_ghs` runs the protocol with one thread per node and returns the
+//! resulting minimum spanning forest. `run_ghs_with` can instead multiplex the
+//! nodes over a fixed pool of workers (`Backend::Pool`) or run every node
+//! in a seeded, single-threaded simulator (`Backend::Simulated`), and
+//! `run_ghs_scheduled` lets a `Scheduler` pick the simulator's delivery order.
+//! `Node`, `Message`, `State` and `Status` are exported for callers who want to
+//! drive the state machines themselves.
+//! Setting `Options::trace` records every node event of a run as JSON Lines;
+//! `Replay` re-executes such a trace step by step.
+//! `dot::to_dot` renders a graph and the outcome of a run for Graphviz.
+//!
+//! Everything is generic over the edge weight type through the `Weight` trait,
+//! implemented for `i32`, `i64`, `u32`, `u64` and `TotalF64`.
+
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::Undirected;
+use snapshot::SnapshotRecorder;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+pub mod dot;
+pub mod generate;
+pub mod input;
+mod json;
+pub mod mst;
+pub mod node;
+pub mod output;
+pub mod pool;
+pub mod replay;
+pub mod rng;
+pub mod runner;
+pub mod sim;
+pub mod snapshot;
+pub mod stats;
+pub mod trace;
+pub mod transport;
+pub mod validate;
+pub mod weight;
+
+pub use input::{parse_graph, parse_graph_as, Format, ParseError, ParseErrorKind};
+pub use mst::{get_mst_from_data, kruskal, verify, Mst, MstComponent, MstEdge, Verification};
+pub use node::{BestWeight, EdgeKey, Message, Node, NodeSnapshot, State, Status};
+pub use replay::{Replay, ReplayError, ReplayErrorKind};
+pub use sim::{Link, RandomScheduler, Scheduler};
+pub use snapshot::LevelSnapshot;
+pub use stats::Stats;
+pub use trace::Tracer;
+pub use transport::Transport;
+pub use validate::{validate_graph, Problem, ValidationError};
+pub use weight::{TotalF64, Weight};
+
+/// Executor that drives the `Node` state machines.
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub enum Backend {
+    /// One OS thread per node, communicating over channels.
+    #[default]
+    Threads,
+    /// Nodes multiplexed over `threads` worker threads, each node running only
+    /// when it has messages; for graphs with more nodes than the system allows threads.
+    Pool { threads: usize },
+    /// All nodes on the calling thread; a scheduler seeded with `seed` picks
+    /// the next message to deliver, so runs are reproducible.
+    Simulated { seed: u64 },
+}
+
+/// Settings for a GHS run.
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    /// Compare edges by `(weight, min(u, v), max(u, v))` so that graphs with
+    /// repeated weights are accepted and yield the lexicographically smallest MST.
+    pub tie_break: bool,
+    pub backend: Backend,
+    /// Records the graph and every node event of the run.
+    pub trace: Option<Arc<Tracer>>,
+    /// Take a `LevelSnapshot` every time a merge or an absorb completes, and one at the end.
+    pub snapshots: bool,
+}
+
+/// Outcome of `run_ghs_with`: the forest plus statistics about the run.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Run<W> {
+    pub mst: Mst<W>,
+    pub stats: Stats,
+    /// Final level, fragment name, state and edge statuses of every node.
+    pub nodes: HashMap<NodeIndex, NodeSnapshot<W>>,
+    /// With `Options::snapshots`, one snapshot per completed merge or absorb and
+    /// a final one, in the order taken.
+    pub snapshots: Vec<LevelSnapshot<W>>,
+}
+
+/// Computes the minimum spanning forest of `graph` with the GHS protocol.
+///
+/// Every component runs the protocol independently and isolated nodes halt
+/// right away. Edge weights must be unique and the graph free of self-loops
+/// and parallel edges; otherwise the problems found by `validate_graph` are
+/// returned and the protocol is not started.
+pub fn run_ghs<N, W: Weight>(
+    graph: &Graph<N, W, Undirected>,
+) -> Result<Mst<W>, ValidationError<W>> {
+    run_ghs_with(graph, &Options::default()).map(|run| run.mst)
+}
+
+/// Same as `run_ghs`, with explicit `options`, also returning the run's `Stats`.
+pub fn run_ghs_with<N, W: Weight>(
+    graph: &Graph<N, W, Undirected>,
+    options: &Options,
+) -> Result<Run<W>, ValidationError<W>> {
+    run(graph, options, None)
+}
+
+/// Same as `run_ghs_with` on the simulated network, delivering messages in the
+/// order `scheduler` picks; `options.backend` is ignored.
+///
+/// A scheduler that ends the run early leaves nodes that never halted, which
+/// `NodeSnapshot::halted` shows.
+pub fn run_ghs_scheduled<N, W: Weight>(
+    graph: &Graph<N, W, Undirected>,
+    options: &Options,
+    scheduler: &mut dyn Scheduler,
+) -> Result<Run<W>, ValidationError<W>> {
+    run(graph, options, Some(scheduler))
+}
+
+fn run<N, W: Weight>(
+    graph: &Graph<N, W, Undirected>,
+    options: &Options,
+    scheduler: Option<&mut dyn Scheduler>,
+) -> Result<Run<W>, ValidationError<W>> {
+    if let Err(mut err) = validate_graph(graph) {
+        err.problems.retain(|problem| match problem {
+            Problem::DuplicateWeight { .. } => !options.tie_break,
+            Problem::IsolatedNode(_) | Problem::Disconnected { .. } => false,
+            Problem::SelfLoop { .. } | Problem::ParallelEdges { .. } => true,
+        });
+        if !err.problems.is_empty() {
+            return Err(err);
+        }
+    }
+    if let Some(tracer) = &options.trace {
+        tracer.record_graph(graph, options);
+    }
+    let recorder = if options.snapshots {
+        Some(Arc::new(SnapshotRecorder::new()))
+    } else {
+        None
+    };
+    let start = Instant::now();
+    let (nodes, branches, mut stats) = match (scheduler, options.backend) {
+        (Some(scheduler), _) => sim::run_scheduled(graph, options, scheduler, recorder.as_ref()),
+        (None, Backend::Threads) => runner::run_threads(graph, options, recorder.as_ref()),
+        (None, Backend::Pool { threads }) => {
+            pool::run_pool(graph, options, threads, recorder.as_ref())
+        }
+        (None, Backend::Simulated { seed }) => {
+            sim::run_simulated(graph, options, seed, recorder.as_ref())
+        }
+    };
+    stats.elapsed = start.elapsed();
+    if let Some(tracer) = &options.trace {
+        tracer.record_end();
+    }
+    let snapshots = recorder.map_or(vec![], |recorder| {
+        recorder.record_end(&nodes);
+        recorder.take_snapshots()
+    });
+    Ok(Run {
+        mst: get_mst_from_data(branches, graph),
+        stats,
+        nodes,
+        snapshots,
+    })
+}
+
+/// Same as `run_ghs`, for a graph given as `(u, v, weight)` edges.
+pub fn run_ghs_from_edges<W: Weight>(
+    edges: &[(u32, u32, W)],
+) -> Result<Mst<W>, ValidationError<W>> {
+    let mut graph: Graph<i32, W, Undirected> = Graph::default();
+    graph.extend_with_edges(edges);
+    run_ghs(&graph)
+}
@@ -1,249 +1,375 @@
-use petgraph::graph::{Graph, NodeIndex};
-use petgraph::visit::EdgeRef;
+mod cli;
+
+use cli::{Command, GenerateArgs, Mode, ReplayArgs, RunArgs, StatsFormat, WeightType};
+use ghs::replay::Action;
+use ghs::{Format, LevelSnapshot, Node, Options, Replay, TotalF64, Tracer, Weight};
+use petgraph::graph::Graph;
 use petgraph::Undirected;
-use std::clone::Clone;
-use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, Read, Write};
+use std::path::Path;
 use std::str::FromStr;
-use std::sync::atomic::AtomicBool;
-use std::sync::mpsc::TryRecvError;
-use std::sync::mpsc::{self, Receiver, Sender};
-use std::sync::{Arc, RwLock};
-use std::thread;
-use std::{env, process};
-
-mod node;
-use node::*;
-
-fn read_graph() -> Graph<i32, i32, Undirected> {
-    let mut args = env::args();
-    if args.len() != 2 {
-        println!("Usage: {} <input-file>", args.next().unwrap());
-        process::exit(1);
+use std::sync::{mpsc, Arc};
+use std::{env, process, thread};
+
+fn main() {
+    let command = match cli::parse(env::args().skip(1)) {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("error: {}\nRun 'ghs --help' for usage.", err);
+            process::exit(1);
+        }
+    };
+    match command {
+        Command::Help(text) => print!("{}", text),
+        Command::Run(args) => match args.weights {
+            WeightType::I32 => run::<i32>(&args),
+            WeightType::I64 => run::<i64>(&args),
+            WeightType::U32 => run::<u32>(&args),
+            WeightType::U64 => run::<u64>(&args),
+            WeightType::F64 => run::<TotalF64>(&args),
+        },
+        Command::Generate(args) => generate(&args),
+        Command::Replay(args) => replay_main(&args),
     }
-    let input_file = args.nth(1).unwrap();
-
-    let input_buffer = std::fs::read_to_string(input_file).expect("Unable to open input file");
-    let input_buffer = input_buffer.as_str();
-    let mut lines = input_buffer.lines();
-    let lines_ref = &mut lines;
-    let nodes = lines_ref.next().unwrap();
-    let _nodes = u32::from_str(nodes).unwrap();
-    //println!("No. of Nodes: {:?}", nodes);
-    let mut graph: Graph<i32, i32, Undirected> = Graph::default();
-    let mut edges_vec = vec![];
-    let mut _edges = 0;
-    for line in lines {
-        _edges += 1;
-        let tuple_vec: Vec<&str> = line
-            .trim_matches(|p| p == '(' || p == ')')
-            .split(',')
-            .collect();
-        let tuple_vec: Vec<i32> = tuple_vec
-            .iter()
-            .map(|s| i32::from_str(s.trim()).unwrap())
-            .collect::<Vec<i32>>();
-        let tuple = (tuple_vec[0] as u32, tuple_vec[1] as u32, tuple_vec[2]);
-        edges_vec.push(tuple);
+}
+
+/// Contents of `path`, or of stdin when `path` is `-`.
+fn read_input(path: &str) -> String {
+    let result = if path == "-" {
+        let mut text = String::new();
+        std::io::stdin().read_to_string(&mut text).map(|_| text)
+    } else {
+        std::fs::read_to_string(path)
+    };
+    match result {
+        Ok(text) => text,
+        Err(err) => {
+            eprintln!("error: unable to open '{}': {}", path, err);
+            process::exit(1);
+        }
     }
-    //println!("No. of Edges: {}", edges);
-    graph.extend_with_edges(&edges_vec[..]);
-    //println!("{:?}", graph);
-    graph
 }
 
-fn get_mst_from_data(
-    data: HashMap<NodeIndex, HashMap<NodeIndex, Status>>,
-    graph: Arc<RwLock<Graph<i32, i32, Undirected>>>,
-) -> Vec<(NodeIndex, NodeIndex, i32)> {
-    let mut pairs = vec![];
-    for (node_index, status_map) in data {
-        for (nbr_index, status) in status_map {
-            if status == Status::Branch {
-                let pair = if node_index < nbr_index {
-                    (node_index, nbr_index)
-                } else {
-                    (nbr_index, node_index)
-                };
-                if !pairs.contains(&pair) {
-                    pairs.push(pair);
+/// Writes `text` to `output_file`, or to stdout when there is none.
+fn write_output(output_file: Option<&str>, text: &str) {
+    match output_file {
+        Some(output_file) => {
+            if let Err(err) = std::fs::write(output_file, text) {
+                eprintln!("error: unable to write '{}': {}", output_file, err);
+                process::exit(1);
+            }
+        }
+        None => print!("{}", text),
+    }
+}
+
+/// Runs `run`, `verify` or `stats` on the input graph.
+fn run<W: Weight + FromStr>(args: &RunArgs) {
+    let input_file = &args.input_file;
+    let input_name = if input_file == "-" {
+        "<stdin>"
+    } else {
+        input_file.as_str()
+    };
+    let input_buffer = read_input(input_file);
+    let format = args
+        .format
+        .unwrap_or_else(|| Format::detect(input_file, &input_buffer));
+    let graph = match ghs::parse_graph_as::<W>(&input_buffer, format) {
+        Ok(graph) => graph,
+        Err(err) => {
+            eprintln!("{}:{}", input_name, err);
+            process::exit(1);
+        }
+    };
+
+    let mut options = Options {
+        tie_break: args.tie_break,
+        backend: args.backend,
+        trace: None,
+        snapshots: args.snapshots_dir.is_some(),
+    };
+    if let Some(trace_file) = &args.trace_file {
+        match File::create(trace_file) {
+            /* Unbuffered, so that the trace of a run that hangs survives killing it */
+            Ok(file) => options.trace = Some(Arc::new(Tracer::new(file))),
+            Err(err) => {
+                eprintln!("error: unable to create '{}': {}", trace_file, err);
+                process::exit(1);
+            }
+        }
+    }
+
+    let result = match args.timeout {
+        None => ghs::run_ghs_with(&graph, &options),
+        Some(timeout) => {
+            /* The run cannot be cancelled; on timeout the process exits and takes it along */
+            let (tx, rx) = mpsc::channel();
+            let (graph, options) = (graph.clone(), options.clone());
+            thread::spawn(move || {
+                let _ = tx.send(ghs::run_ghs_with(&graph, &options));
+            });
+            match rx.recv_timeout(timeout) {
+                Ok(result) => result,
+                Err(_) => {
+                    eprintln!(
+                        "{}: the run did not finish within {} s",
+                        input_name,
+                        timeout.as_secs_f64()
+                    );
+                    process::exit(2);
                 }
             }
         }
+    };
+    let run = match result {
+        Ok(run) => run,
+        Err(err) => {
+            eprintln!("{}: {}", input_name, err);
+            process::exit(1);
+        }
+    };
+
+    if let Some(tracer) = &options.trace {
+        if let Err(err) = tracer.finish() {
+            eprintln!("error: unable to write the trace: {}", err);
+            process::exit(1);
+        }
+    }
+
+    match args.mode {
+        Mode::Run => {
+            let output = ghs::output::format_mst(args.output, &graph, &run);
+            write_output(args.output_file.as_deref(), &output);
+            match args.stats {
+                Some(StatsFormat::Text) => eprintln!("{}", run.stats),
+                Some(StatsFormat::Json) => eprintln!("{}", run.stats.to_json()),
+                None => {}
+            }
+        }
+        Mode::Stats => match args.stats {
+            Some(StatsFormat::Json) => println!("{}", run.stats.to_json()),
+            _ => println!("{}", run.stats),
+        },
+        Mode::Verify => {}
     }
-    //println!("Pairs: {:?}", pairs);
-
-    let mut weight_map = HashMap::new();
-    let graph = graph.read().unwrap();
-    for edge in graph.edge_references() {
-        let source = edge.source();
-        let target = edge.target();
-        let weight = *edge.weight();
-        weight_map.insert((source, target), weight);
+
+    if let Some(dot) = &args.dot {
+        let text = ghs::dot::to_dot(&graph, &run.nodes, dot.color_fragments);
+        if let Err(err) = std::fs::write(&dot.file, text) {
+            eprintln!("error: unable to write '{}': {}", dot.file, err);
+            process::exit(1);
+        }
     }
 
-    let mut triplets = vec![];
-    for pair in pairs {
-        let (one, two) = pair;
-        let mut new_pair = (one, two);
-        let weight = if weight_map.get(&new_pair) == None {
-            new_pair = (two, one);
-            weight_map
-                .get(&new_pair)
-                .expect("Error while getting a pair weight from weight_map:")
+    if let Some(dir) = &args.snapshots_dir {
+        if let Err(err) = write_snapshots(dir, &graph, &run.snapshots) {
+            eprintln!("error: unable to write snapshots to '{}': {}", dir, err);
+            process::exit(1);
+        }
+    }
+
+    if args.verify || args.mode == Mode::Verify {
+        let verification = ghs::verify(&run.mst, &ghs::kruskal(&graph));
+        if args.mode == Mode::Verify {
+            println!("{}", verification);
         } else {
-            weight_map
-                .get(&new_pair)
-                .expect("Error while getting a pair weight from weight_map:")
-        };
-        let (one, two) = new_pair;
-        let triplet = (one, two, *weight);
-        triplets.push(triplet);
+            eprintln!("{}", verification);
+        }
+        if !verification.edges_equal() {
+            process::exit(1);
+        }
     }
-    //println!("Triplets: {:?}", triplets);
-    triplets.sort_unstable_by(|(_, _, weight1), (_, _, weight2)| weight1.cmp(weight2));
-    //println!("Sorted Triplets: {:?}", triplets);
-    triplets
 }
 
-fn main() {
-    let graph: Arc<RwLock<Graph<i32, i32, Undirected>>> = Arc::new(RwLock::new(read_graph()));
-    let orig_mapping: Arc<RwLock<HashMap<NodeIndex, RwLock<Node>>>> =
-        Arc::new(RwLock::new(HashMap::new()));
-    let stop = Arc::new(RwLock::new(AtomicBool::new(false)));
-    for node_index in graph
-        .read()
-        .expect("Error while reading 'graph':")
-        .node_indices()
-    {
-        //println!("creating node and mapping..");
-        let node = Node::new(Arc::clone(&graph), node_index, Arc::clone(&stop));
-        let mut mapping = orig_mapping.write().unwrap();
-        mapping.insert(node_index, RwLock::new(node));
-        //println!("created node and mapping..");
+fn generate(args: &GenerateArgs) {
+    let generated = match args.edges {
+        Some(edges) => match ghs::generate::random_connected(args.nodes, edges, args.seed) {
+            Ok(generated) => generated,
+            Err(err) => {
+                eprintln!("error: {}", err);
+                process::exit(1);
+            }
+        },
+        None => ghs::generate::generate(args.family, args.nodes, args.degree, args.seed),
+    };
+    write_output(args.output_file.as_deref(), &generated.to_tuple_format());
+}
+
+/// Writes `snapshots.json` and one `snapshot-<n>.dot` per snapshot into `dir`.
+fn write_snapshots<W: Weight>(
+    dir: &str,
+    graph: &Graph<i32, W, Undirected>,
+    snapshots: &[LevelSnapshot<W>],
+) -> std::io::Result<()> {
+    let dir = Path::new(dir);
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(
+        dir.join("snapshots.json"),
+        ghs::snapshot::to_json(snapshots),
+    )?;
+    for (id, snapshot) in snapshots.iter().enumerate() {
+        let dot = ghs::dot::to_dot(graph, &snapshot.nodes, true);
+        std::fs::write(dir.join(format!("snapshot-{:03}.dot", id)), dot)?;
     }
+    Ok(())
+}
 
-    let mut sender_mapping: HashMap<NodeIndex, Sender<Message>> = HashMap::new();
-    let mut receiver_mapping: HashMap<NodeIndex, Receiver<Message>> = HashMap::new();
-    for node_index in graph
-        .read()
-        .expect("Error while reading 'graph':")
-        .node_indices()
-    {
-        let (sender, receiver) = mpsc::channel();
-        sender_mapping.insert(node_index, sender);
-        receiver_mapping.insert(node_index, receiver);
+fn replay_main(args: &ReplayArgs) {
+    let trace_file = &args.trace_file;
+    let text = read_input(trace_file);
+    let (trace_file, step) = (trace_file.as_str(), args.step);
+    /* An unreadable header is reported by 'Replay::from_trace' */
+    match ghs::replay::trace_weights(&text).as_deref() {
+        Some("i64") => replay::<i64>(trace_file, &text, step),
+        Some("u32") => replay::<u32>(trace_file, &text, step),
+        Some("u64") => replay::<u64>(trace_file, &text, step),
+        Some("f64") => replay::<TotalF64>(trace_file, &text, step),
+        _ => replay::<i32>(trace_file, &text, step),
     }
+}
 
-    let mut handles = vec![];
-    for node_index in graph
-        .read()
-        .expect("Error while reading 'graph':")
-        .node_indices()
-    {
-        let move_mapping = Arc::clone(&orig_mapping);
-        let sender_mapping = sender_mapping.clone();
-        let receiver = receiver_mapping.remove(&node_index).unwrap();
-        let handle = thread::Builder::new()
-            .name(node_index.index().to_string())
-            .spawn(move || {
-                //println!("Thread no. {:?} started!", node_index);
-                let receiver = receiver;
-                let sender_mapping = sender_mapping;
-                let mapping = move_mapping.read().unwrap();
-                let node = mapping.get(&node_index).unwrap();
-                /* is it okay to keep 'node' mutated throught this thread's scope? */
-                let mut node = node.write().unwrap();
-                /* Should we wakeup (initialize) all the nodes? */
-                node.initialize(&sender_mapping);
-                loop {
-                    {
-                        if *node.stop.write().unwrap().get_mut() {
-                            break;
-                        }
-                    }
-                    /* Blocking receive? OR Non-blocking? */
-                    let recv = receiver.try_recv();
-                    let msg = match recv {
-                        Err(TryRecvError::Empty) => {
-                            continue;
-                        }
-                        Err(TryRecvError::Disconnected) => {
-                            break;
-                        }
-                        Ok(message) => message,
-                    };
-                    match msg {
-                        Message::Connect(_, _sender_index) => {
-                            /*println!(
-                                "Thread [{:?}]: Got message: {:?} from {:?}",
-                                node_index, msg, sender_index
-                            );*/
-                            node.process_connect(msg, &sender_mapping);
-                        }
-                        Message::Initiate(_, _, _, _sender_index) => {
-                            /*println!(
-                                "Thread [{:?}]: Got message: {:?} from {:?}",
-                                node_index, msg, sender_index
-                            );*/
-                            node.process_initiate(msg, &sender_mapping);
-                        }
-                        Message::Test(_, _, _sender_index) => {
-                            /*println!(
-                                "Thread [{:?}]: Got message: {:?} from {:?}",
-                                node_index, msg, sender_index
-                            );*/
-                            node.process_test(msg, &sender_mapping);
-                        }
-                        Message::Accept(_sender_index) => {
-                            /*println!(
-                                "Thread [{:?}]: Got message: {:?} from {:?}",
-                                node_index, msg, sender_index
-                            );*/
-                            node.process_accept(msg, &sender_mapping);
-                        }
-                        Message::Reject(_sender_index) => {
-                            /*println!(
-                                "Thread [{:?}]: Got message: {:?} from {:?}",
-                                node_index, msg, sender_index
-                            );*/
-                            node.process_reject(msg, &sender_mapping);
-                        }
-                        Message::Report(_, _sender_index) => {
-                            /*println!(
-                                "Thread [{:?}]: Got message: {:?} from {:?}",
-                                node_index, msg, sender_index
-                            );*/
-                            node.process_report(msg, &sender_mapping);
-                            //println!("node.best_wt: {:?}", node.best_wt);
-                        }
-                        Message::ChangeRoot(_sender_index) => {
-                            /*println!(
-                                "Thread [{:?}]: Got message: {:?} from {:?}",
-                                node_index, msg, sender_index
-                            );*/
-                            node.process_change_root(msg, &sender_mapping);
-                        }
+/// Replays a trace to the end, or under the control of stdin commands with `step`.
+fn replay<W: Weight + FromStr>(trace_file: &str, text: &str, step: bool) {
+    let mut replay = match Replay::<W>::from_trace(text) {
+        Ok(replay) => replay,
+        Err(err) => {
+            eprintln!("{}:{}", trace_file, err);
+            process::exit(1);
+        }
+    };
+    if step {
+        println!("{} steps; 'h' lists the commands", replay.steps().len());
+        let stdin = std::io::stdin();
+        let mut lines = stdin.lock().lines();
+        loop {
+            print!("(replay) ");
+            std::io::stdout()
+                .flush()
+                .expect("Error while flushing stdout:");
+            let line = match lines.next() {
+                Some(Ok(line)) => line,
+                _ => break,
+            };
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                [] | ["s"] => replay_steps(trace_file, &mut replay, 1, true),
+                ["s", count] => match count.parse() {
+                    Ok(count) => replay_steps(trace_file, &mut replay, count, true),
+                    Err(_) => println!("'{}' is not a step count", count),
+                },
+                ["c"] => replay_steps(trace_file, &mut replay, usize::MAX, false),
+                ["p"] => {
+                    for node in replay.nodes() {
+                        print_node(&replay, node);
                     }
                 }
-                //println!("Thread no. {:?} Stopped!", node_index);
-                (node_index, node.status.clone())
-            });
-        handles.push(handle);
-    }
-    //println!("All threads finished!");
-    let mut data: HashMap<NodeIndex, HashMap<NodeIndex, Status>> = HashMap::new();
-    for handle in handles {
-        let (node_index, status_map) = handle
-            .expect("Error while unwrapping 'handle':")
-            .join()
-            .expect("Error while unwrapping 'handle.join()':");
-        data.insert(node_index, status_map);
+                ["p", node] => match node.parse::<usize>() {
+                    Ok(node) if node < replay.nodes().len() => {
+                        print_node(&replay, &replay.nodes()[node])
+                    }
+                    _ => println!("'{}' is not a node", node),
+                },
+                ["q"] => return,
+                _ => println!(
+                    "s [n]  replay the next n steps (default 1; an empty line does the same)\n\
+                     c      replay every remaining step\n\
+                     p [n]  print node n, or every node\n\
+                     q      quit"
+                ),
+            }
+        }
+    } else {
+        replay_steps(trace_file, &mut replay, usize::MAX, false);
     }
+}
 
-    let mst = get_mst_from_data(data, graph);
+/// Replays up to `count` steps; at the end of the trace, reports the nodes that did not halt.
+fn replay_steps<W: Weight + FromStr>(
+    trace_file: &str,
+    replay: &mut Replay<W>,
+    count: usize,
+    verbose: bool,
+) {
+    for _ in 0..count {
+        let step = match replay.step() {
+            Ok(Some(step)) => step.clone(),
+            Ok(None) => break,
+            Err(err) => {
+                eprintln!("{}:{}", trace_file, err);
+                let node = match err.kind {
+                    ghs::ReplayErrorKind::StateMismatch { node, .. }
+                    | ghs::ReplayErrorKind::Diverged { node, .. } => node,
+                    _ => process::exit(1),
+                };
+                print_node(replay, &replay.nodes()[node.index()]);
+                process::exit(1);
+            }
+        };
+        if verbose {
+            let action = match &step.action {
+                Action::Init => String::from("wakes up"),
+                Action::Receive(msg) => format!("receives {}", msg),
+            };
+            println!(
+                "step {}/{} (line {}): node {} {}",
+                replay.position(),
+                replay.steps().len(),
+                step.line,
+                step.node.index(),
+                action
+            );
+            print_node(replay, &replay.nodes()[step.node.index()]);
+        }
+    }
+    if replay.position() == replay.steps().len() {
+        let running: Vec<&Node<W>> = replay
+            .nodes()
+            .iter()
+            .filter(|node| !node.is_halted())
+            .collect();
+        println!(
+            "replayed {} steps; every node sent the recorded messages",
+            replay.steps().len()
+        );
+        if !replay.is_complete() {
+            println!("the trace ends before the run finished");
+        }
+        if !running.is_empty() {
+            println!(
+                "{} of {} nodes did not halt:",
+                running.len(),
+                replay.nodes().len()
+            );
+            for node in running {
+                print_node(replay, node);
+            }
+        }
+    }
+}
 
-    for triplet in mst {
-        let (one, two, three) = triplet;
-        println!("({}, {}, {})", one.index(), two.index(), three);
+fn print_node<W: Weight + FromStr>(replay: &Replay<W>, node: &Node<W>) {
+    println!(
+        "node {}: {:?}, level {}, name {}, best_wt {}{}",
+        node.index().index(),
+        node.state(),
+        node.level(),
+        node.name()
+            .map_or(String::from("none"), |name| name.to_string()),
+        node.best_wt(),
+        if node.is_halted() { ", halted" } else { "" }
+    );
+    let status: Vec<String> = replay
+        .neighbours(node.index())
+        .iter()
+        .map(|nbr| match node.status(*nbr) {
+            Some(status) => format!("{} {:?}", nbr.index(), status),
+            None => format!("{} -", nbr.index()),
+        })
+        .collect();
+    println!("  status: {}", status.join(", "));
+    for msg in node.deferred() {
+        println!("  deferred: {}", msg);
     }
 }
@@ -0,0 +1,204 @@
+use crate::validate::components;
+use crate::weight::Weight;
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use petgraph::Undirected;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+/// An edge of the computed minimum spanning tree.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct MstEdge<W> {
+    pub source: NodeIndex,
+    pub target: NodeIndex,
+    pub weight: W,
+}
+
+/// The spanning tree of one connected component of the input.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct MstComponent<W> {
+    /// Nodes of the component, sorted by index.
+    pub nodes: Vec<NodeIndex>,
+    /// Tree edges of the component, sorted by weight.
+    pub edges: Vec<MstEdge<W>>,
+}
+
+/// Result of a GHS run: a minimum spanning forest.
+///
+/// For a connected input there is a single component and `edges` is its tree.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct Mst<W> {
+    /// All forest edges sorted by weight.
+    pub edges: Vec<MstEdge<W>>,
+    /// The forest split by connected component, ordered by smallest node index.
+    pub components: Vec<MstComponent<W>>,
+}
+
+impl<W: Weight> Mst<W> {
+    pub fn total_weight(&self) -> W::Total {
+        self.edges.iter().map(|edge| edge.weight.to_total()).sum()
+    }
+}
+
+/// Collects the edges the nodes marked as `Status::Branch` into an `Mst`.
+///
+/// `data` holds the branch edges of every node, so each tree edge usually
+/// appears twice, once from either end; it is kept once.
+pub fn get_mst_from_data<N, W: Weight>(
+    data: Vec<MstEdge<W>>,
+    graph: &Graph<N, W, Undirected>,
+) -> Mst<W> {
+    let mut pairs = HashSet::new();
+    let edges = data
+        .into_iter()
+        .filter(|edge| pairs.insert((edge.source.min(edge.target), edge.source.max(edge.target))))
+        .collect();
+    forest_from_edges(edges, graph)
+}
+
+/// Sorts `edges` by weight and groups them by the connected components of `graph`.
+fn forest_from_edges<N, W: Weight>(
+    mut edges: Vec<MstEdge<W>>,
+    graph: &Graph<N, W, Undirected>,
+) -> Mst<W> {
+    edges.sort_unstable_by_key(|edge| (edge.weight, edge.source, edge.target));
+
+    let mut component_of = HashMap::new();
+    let mut grouped = vec![];
+    for (id, nodes) in components(graph).into_iter().enumerate() {
+        for node in &nodes {
+            component_of.insert(*node, id);
+        }
+        grouped.push(MstComponent {
+            nodes,
+            edges: vec![],
+        });
+    }
+    for edge in &edges {
+        grouped[component_of[&edge.source]].edges.push(*edge);
+    }
+    Mst {
+        edges,
+        components: grouped,
+    }
+}
+
+/// Sequential Kruskal reference for checking a GHS run.
+///
+/// Edges are taken in `(weight, min(u, v), max(u, v))` order, so ties resolve
+/// the same way as GHS with tie-breaking enabled.
+pub fn kruskal<N, W: Weight>(graph: &Graph<N, W, Undirected>) -> Mst<W> {
+    let mut sorted: Vec<_> = graph
+        .edge_references()
+        .map(|edge| {
+            let (source, target) = (edge.source(), edge.target());
+            (
+                (*edge.weight(), source.min(target), source.max(target)),
+                source,
+                target,
+            )
+        })
+        .collect();
+    sorted.sort_unstable();
+
+    /* union-find with path halving */
+    let mut parent: Vec<usize> = (0..graph.node_count()).collect();
+    fn find(parent: &mut [usize], mut node: usize) -> usize {
+        while parent[node] != node {
+            parent[node] = parent[parent[node]];
+            node = parent[node];
+        }
+        node
+    }
+
+    let mut edges = vec![];
+    for ((weight, _, _), source, target) in sorted {
+        let root_source = find(&mut parent, source.index());
+        let root_target = find(&mut parent, target.index());
+        if root_source != root_target {
+            parent[root_source] = root_target;
+            edges.push(MstEdge {
+                source,
+                target,
+                weight,
+            });
+        }
+    }
+    forest_from_edges(edges, graph)
+}
+
+/// Comparison of a GHS result against the sequential reference.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Verification<W: Weight> {
+    pub ghs_weight: W::Total,
+    pub reference_weight: W::Total,
+    /// Edges chosen by GHS but not by the reference.
+    pub only_in_ghs: Vec<MstEdge<W>>,
+    /// Edges chosen by the reference but not by GHS.
+    pub only_in_reference: Vec<MstEdge<W>>,
+}
+
+impl<W: Weight> Verification<W> {
+    /// Whether both runs picked the same edge set.
+    pub fn edges_equal(&self) -> bool {
+        self.only_in_ghs.is_empty() && self.only_in_reference.is_empty()
+    }
+}
+
+impl<W: Weight> fmt::Display for Verification<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "ghs total weight: {}", self.ghs_weight)?;
+        writeln!(f, "reference total weight: {}", self.reference_weight)?;
+        write!(
+            f,
+            "edge sets: {}",
+            if self.edges_equal() {
+                "equal"
+            } else {
+                "differ"
+            }
+        )?;
+        for (label, edges) in &[
+            ("only in ghs", &self.only_in_ghs),
+            ("only in reference", &self.only_in_reference),
+        ] {
+            for edge in edges.iter() {
+                write!(
+                    f,
+                    "\n{}: ({}, {}, {})",
+                    label,
+                    edge.source.index(),
+                    edge.target.index(),
+                    edge.weight
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compares `ghs` with `reference`, treating edges as unordered node pairs.
+pub fn verify<W: Weight>(ghs: &Mst<W>, reference: &Mst<W>) -> Verification<W> {
+    fn pairs<W>(mst: &Mst<W>) -> BTreeSet<(NodeIndex, NodeIndex)> {
+        mst.edges
+            .iter()
+            .map(|edge| (edge.source.min(edge.target), edge.source.max(edge.target)))
+            .collect()
+    }
+    let difference = |left: &Mst<W>, right: &Mst<W>| -> Vec<MstEdge<W>> {
+        let right = pairs(right);
+        left.edges
+            .iter()
+            .filter(|edge| {
+                !right.contains(&(edge.source.min(edge.target), edge.source.max(edge.target)))
+            })
+            .copied()
+            .collect()
+    };
+    Verification {
+        ghs_weight: ghs.total_weight(),
+        reference_weight: reference.total_weight(),
+        only_in_ghs: difference(ghs, reference),
+        only_in_reference: difference(reference, ghs),
+    }
+}
@@ -3,413 +3,549 @@ use petgraph::visit::EdgeRef;
 use petgraph::Undirected;
 use std::clone::Clone;
 use std::cmp::PartialEq;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
 use std::marker::Copy;
-use std::sync::atomic::AtomicBool;
-use std::sync::mpsc::Sender;
-use std::sync::{Arc, RwLock};
+use std::sync::Arc;
 
-#[derive(PartialEq, Copy, Clone, Debug)]
+use crate::mst::MstEdge;
+use crate::snapshot::SnapshotRecorder;
+use crate::stats::Stats;
+use crate::trace;
+use crate::trace::Tracer;
+use crate::transport::Transport;
+use crate::weight::Weight;
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
 pub enum State {
     Sleep,
     Find,
     Found,
 }
-#[derive(PartialEq, Clone, Debug)]
-pub enum Message {
-    Connect(u32, NodeIndex),              /* level */
-    Initiate(u32, i32, State, NodeIndex), /* level, name, state */
-    Test(u32, i32, NodeIndex),            /* level, name */
+/// Total order on edges used for every weight comparison and as fragment name.
+///
+/// With tie-breaking the key is `(weight, min(u, v), max(u, v))`, which makes
+/// every edge distinct; without it `low` and `high` are zero and keys compare
+/// by weight alone.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Debug)]
+pub struct EdgeKey<W> {
+    pub weight: W,
+    pub low: u32,
+    pub high: u32,
+}
+impl<W> EdgeKey<W> {
+    /// Key of the edge between `u` and `v`, which has weight `weight`.
+    pub fn new(u: NodeIndex, v: NodeIndex, weight: W, tie_break: bool) -> Self {
+        if tie_break {
+            EdgeKey {
+                weight,
+                low: u.min(v).index() as u32,
+                high: u.max(v).index() as u32,
+            }
+        } else {
+            EdgeKey {
+                weight,
+                low: 0,
+                high: 0,
+            }
+        }
+    }
+}
+/// Best outgoing edge found by a node or subtree during a `Find` phase.
+///
+/// `Infinity` means no outgoing edge was found. Variants are declared in this
+/// order so that `Infinity` compares greater than every edge.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Debug)]
+pub enum BestWeight<W> {
+    Edge(EdgeKey<W>),
+    Infinity,
+}
+impl<W: fmt::Display> fmt::Display for EdgeKey<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.low == self.high {
+            write!(f, "{}", self.weight)
+        } else {
+            write!(f, "{} ({}-{})", self.weight, self.low, self.high)
+        }
+    }
+}
+impl<W: fmt::Display> fmt::Display for BestWeight<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BestWeight::Edge(key) => write!(f, "{}", key),
+            BestWeight::Infinity => write!(f, "inf"),
+        }
+    }
+}
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Message<W> {
+    Connect(u32, NodeIndex),                     /* level */
+    Initiate(u32, EdgeKey<W>, State, NodeIndex), /* level, name, state */
+    Test(u32, EdgeKey<W>, NodeIndex),            /* level, name */
     Accept(NodeIndex),
     Reject(NodeIndex),
-    Report(i32, NodeIndex), /* best_wt */
+    Report(BestWeight<W>, NodeIndex), /* best_wt */
     ChangeRoot(NodeIndex),
+    Halt(NodeIndex),
+}
+impl<W> Message<W> {
+    /// Node that sent the message.
+    pub fn sender(&self) -> NodeIndex {
+        match *self {
+            Message::Connect(_, sender_index)
+            | Message::Initiate(_, _, _, sender_index)
+            | Message::Test(_, _, sender_index)
+            | Message::Accept(sender_index)
+            | Message::Reject(sender_index)
+            | Message::Report(_, sender_index)
+            | Message::ChangeRoot(sender_index)
+            | Message::Halt(sender_index) => sender_index,
+        }
+    }
+}
+impl<W: fmt::Display> fmt::Display for Message<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Message::Connect(level, _) => write!(f, "Connect(level {})", level)?,
+            Message::Initiate(level, name, state, _) => {
+                write!(f, "Initiate(level {}, name {}, {:?})", level, name, state)?
+            }
+            Message::Test(level, name, _) => write!(f, "Test(level {}, name {})", level, name)?,
+            Message::Accept(_) => write!(f, "Accept")?,
+            Message::Reject(_) => write!(f, "Reject")?,
+            Message::Report(best_wt, _) => write!(f, "Report({})", best_wt)?,
+            Message::ChangeRoot(_) => write!(f, "ChangeRoot")?,
+            Message::Halt(_) => write!(f, "Halt")?,
+        }
+        write!(f, " from {}", self.sender().index())
+    }
 }
-#[derive(PartialEq, Copy, Clone)]
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
 pub enum Status {
     Basic,
     Branch,
     Reject,
 }
-pub struct Node {
+/// A node's level, fragment name, state, edge statuses and halted flag at one point of a run.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct NodeSnapshot<W> {
+    pub level: u32,
+    pub name: Option<EdgeKey<W>>,
+    pub state: State,
+    pub status: HashMap<NodeIndex, Status>,
+    /// Whether the node had received (or originated) the termination broadcast.
+    pub halted: bool,
+}
+pub struct Node<W> {
     index: NodeIndex,
     state: State,
-    pub status: HashMap<NodeIndex, Status>,
-    name: i32,
+    /// Neighbours with the keys of the edges to them, in ascending key order.
+    adjacency: Vec<(EdgeKey<W>, NodeIndex)>,
+    /// Status of every edge in `adjacency`, at the same position; empty until `initialize`.
+    status: Vec<Status>,
+    /// Number of edges in `status` that are `Branch`; an edge never stops being one.
+    branches: u32,
+    /// Position of every neighbour in `adjacency`.
+    positions: HashMap<NodeIndex, usize>,
+    /// Position in `adjacency` before which no edge is `Basic` any more; an
+    /// edge never becomes `Basic` again, so `find_min` starts looking here.
+    basic_from: usize,
+    name: Option<EdgeKey<W>>,
     level: u32,
     parent: Option<NodeIndex>,
-    best_wt: i32,
+    best_wt: BestWeight<W>,
     best_node: Option<NodeIndex>,
     rec: u32,
     test_node: Option<NodeIndex>,
-    graph: Arc<RwLock<Graph<i32, i32, Undirected>>>,
-    pub stop: Arc<RwLock<AtomicBool>>,
+    halted: bool,
+    stats: Stats,
+    /// Messages that arrived before this node could handle them, oldest first.
+    deferred: VecDeque<Message<W>>,
+    /// Bumped on every `status` change, so `process` can tell when to retry `deferred`.
+    status_changes: u64,
+    tracer: Option<Arc<Tracer>>,
+    recorder: Option<Arc<SnapshotRecorder<W>>>,
+    /// `progress` and name as last published to `recorder`.
+    published: Option<(u32, State, u64, Option<EdgeKey<W>>)>,
 }
 
-impl Node {
-    pub fn new(
-        graph: Arc<RwLock<Graph<i32, i32, Undirected>>>,
-        index: NodeIndex,
-        stop: Arc<RwLock<AtomicBool>>,
-    ) -> Self {
+impl<W: Weight> Node<W> {
+    /// Node `index` of `graph`, keeping its own copy of the edges to its neighbours.
+    pub fn new<N>(graph: &Graph<N, W, Undirected>, index: NodeIndex, tie_break: bool) -> Self {
+        let mut adjacency: Vec<(EdgeKey<W>, NodeIndex)> = graph
+            .edges(index)
+            .map(|edge| {
+                let nbr = if edge.source() == index {
+                    edge.target()
+                } else {
+                    edge.source()
+                };
+                (EdgeKey::new(index, nbr, *edge.weight(), tie_break), nbr)
+            })
+            .collect();
+        adjacency.sort();
+        let positions = adjacency
+            .iter()
+            .enumerate()
+            .map(|(position, &(_, nbr))| (nbr, position))
+            .collect();
         Node {
             index,
             state: State::Sleep,
-            status: HashMap::new(),
-            name: index.index() as i32,
+            adjacency,
+            status: vec![],
+            branches: 0,
+            positions,
+            basic_from: 0,
+            /* a lone node's fragment has no core edge to be named after */
+            name: None,
             level: 0,
             parent: None,
-            best_wt: std::i32::MAX,
+            best_wt: BestWeight::Infinity,
             best_node: None,
             rec: 0,
             test_node: None,
-            graph,
-            stop,
+            halted: false,
+            stats: Stats::default(),
+            deferred: VecDeque::new(),
+            status_changes: 0,
+            tracer: None,
+            recorder: None,
+            published: None,
         }
     }
-    pub fn initialize(&mut self, sender_mapping: &HashMap<NodeIndex, Sender<Message>>) {
-        //println!("Initializing node {:?}..", self.index);
-        let graph = self.graph.read().expect("Error while reading 'graph':");
-        let edges = graph.edges(self.index);
-        let edge_min = edges
-            .min_by_key(|edge_ref| edge_ref.weight())
-            .expect("Error while finding least weight edge during initialization:");
-        let src = edge_min.source();
-        let target = edge_min.target();
-        let nbr_q = if src == self.index { target } else { src };
-        self.status.insert(nbr_q, Status::Branch);
-        /* Filling up 'status' of every node other than 'nbr_q' to be 'Status::Basic' */
-        for node_index in graph.node_indices() {
-            if node_index != nbr_q {
-                self.status.insert(node_index, Status::Basic);
+    /// Records every event of this node to `tracer` from now on.
+    pub fn set_tracer(&mut self, tracer: Arc<Tracer>) {
+        self.tracer = Some(tracer);
+    }
+    /// Publishes this node's snapshot to `recorder` whenever it changes.
+    pub fn set_recorder(&mut self, recorder: Arc<SnapshotRecorder<W>>) {
+        self.recorder = Some(recorder);
+    }
+    /// Publishes this node's snapshot if it changed since it was last published.
+    fn publish(&mut self) {
+        if let Some(recorder) = &self.recorder {
+            let (level, state, status_changes) = self.progress();
+            let published = Some((level, state, status_changes, self.name));
+            if self.published != published {
+                recorder.publish(self.index, self.snapshot());
+                self.published = published;
             }
         }
+    }
+    pub fn index(&self) -> NodeIndex {
+        self.index
+    }
+    pub fn state(&self) -> State {
+        self.state
+    }
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+    /// Fragment name, the key of its core edge; `None` before the first `Initiate`.
+    pub fn name(&self) -> Option<EdgeKey<W>> {
+        self.name
+    }
+    /// Best outgoing edge found so far in the current `Find` phase.
+    pub fn best_wt(&self) -> BestWeight<W> {
+        self.best_wt
+    }
+    /// Whether this node has received (or originated) the termination broadcast.
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+    /// Copy of this node's level, name, state, edge statuses and halted flag.
+    pub fn snapshot(&self) -> NodeSnapshot<W> {
+        NodeSnapshot {
+            level: self.level,
+            name: self.name,
+            state: self.state,
+            status: self.neighbours().zip(self.status.iter().copied()).collect(),
+            halted: self.halted,
+        }
+    }
+    /// Edges this node has marked `Status::Branch`, with their weights.
+    pub fn branch_edges(&self) -> impl Iterator<Item = MstEdge<W>> + '_ {
+        self.adjacency
+            .iter()
+            .zip(&self.status)
+            .filter(|(_, status)| **status == Status::Branch)
+            .map(move |(&(key, nbr), _)| MstEdge {
+                source: self.index,
+                target: nbr,
+                weight: key.weight,
+            })
+    }
+    /// Messages waiting for this node's level, state or edge statuses to change.
+    pub fn deferred(&self) -> impl Iterator<Item = &Message<W>> {
+        self.deferred.iter()
+    }
+    /// Messages sent and deferred by this node so far.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+    /// Sends `msg` to `to` and counts it in this node's statistics.
+    fn send(&mut self, to: NodeIndex, msg: Message<W>, transport: &mut dyn Transport<W>) {
+        self.trace("send", || {
+            format!(
+                ",\"to\":{},\"msg\":{}",
+                to.index(),
+                trace::message_json(&msg)
+            )
+        });
+        self.stats.count(&msg);
+        /* The receiver may handle `msg` before `process` returns */
+        self.publish();
+        transport.send(self.index, to, msg);
+    }
+    /// Keeps `msg` until this node's state changes.
+    fn defer(&mut self, msg: Message<W>) {
+        self.trace("defer", || {
+            format!(",\"msg\":{}", trace::message_json(&msg))
+        });
+        self.stats.requeued += 1;
+        self.deferred.push_back(msg);
+    }
+    /// Status of the edge to `nbr`; `None` before `initialize` or if `nbr` is not a neighbour.
+    pub fn status(&self, nbr: NodeIndex) -> Option<Status> {
+        let position = *self.positions.get(&nbr)?;
+        self.status.get(position).copied()
+    }
+    /// Status of the edge to `nbr`, which must be a neighbour of an initialized node.
+    fn status_to(&self, nbr: NodeIndex) -> Status {
+        self.status(nbr).expect("Error while reading 'status':")
+    }
+    fn set_status(&mut self, nbr: NodeIndex, status: Status) {
+        let slot = &mut self.status[self.positions[&nbr]];
+        if status == Status::Branch && *slot != Status::Branch {
+            self.branches += 1;
+        }
+        *slot = status;
+        self.status_changes += 1;
+        self.trace("status", || {
+            format!(
+                ",\"nbr\":{},\"status\":{}",
+                nbr.index(),
+                trace::status_json(status)
+            )
+        });
+    }
+    /// Records `event` with this node's level, name and state, followed by `fields`.
+    fn trace(&self, event: &str, fields: impl FnOnce() -> String) {
+        if let Some(tracer) = &self.tracer {
+            let node = format!(
+                ",\"node\":{},\"level\":{},\"name\":{},\"state\":{}",
+                self.index.index(),
+                self.level,
+                trace::name_json(&self.name),
+                trace::state_json(self.state)
+            );
+            tracer.record(event, &(node + &fields()));
+        }
+    }
+    /// Everything a deferred message can be waiting on.
+    fn progress(&self) -> (u32, State, u64) {
+        (self.level, self.state, self.status_changes)
+    }
+    /// Key of the edge between `self.index` and `nbr`.
+    fn edge_key_to(&self, nbr: NodeIndex) -> EdgeKey<W> {
+        self.adjacency[self.positions[&nbr]].0
+    }
+    /// Neighbours in ascending order of the key of the edge to them.
+    pub fn neighbours(&self) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.adjacency.iter().map(|&(_, nbr)| nbr)
+    }
+    /// Handles `msg`, then retries deferred messages if the node's state changed.
+    ///
+    /// Messages arrive in FIFO order per link. As in the GHS paper, a message
+    /// that has to wait does not block later ones; deferred messages are retried
+    /// in arrival order.
+    pub fn process(&mut self, msg: Message<W>, transport: &mut dyn Transport<W>) {
+        self.trace("receive", || {
+            format!(",\"msg\":{}", trace::message_json(&msg))
+        });
+        let before = self.progress();
+        self.dispatch(msg, transport);
+        if self.progress() != before {
+            self.retry_deferred(transport);
+        }
+        self.publish();
+    }
+    /// Re-examines deferred messages in arrival order until a pass makes no progress.
+    fn retry_deferred(&mut self, transport: &mut dyn Transport<W>) {
+        loop {
+            let before = self.progress();
+            for msg in std::mem::take(&mut self.deferred) {
+                self.trace("retry", || {
+                    format!(",\"msg\":{}", trace::message_json(&msg))
+                });
+                self.dispatch(msg, transport);
+            }
+            if self.deferred.is_empty() || self.progress() == before {
+                break;
+            }
+        }
+    }
+    /// Passes `msg` to the matching `process_*` handler.
+    fn dispatch(&mut self, msg: Message<W>, transport: &mut dyn Transport<W>) {
+        match msg {
+            Message::Connect(..) => self.process_connect(msg, transport),
+            Message::Initiate(..) => self.process_initiate(msg, transport),
+            Message::Test(..) => self.process_test(msg, transport),
+            Message::Accept(..) => self.process_accept(msg, transport),
+            Message::Reject(..) => self.process_reject(msg, transport),
+            Message::Report(..) => self.process_report(msg, transport),
+            Message::ChangeRoot(..) => self.process_change_root(msg, transport),
+            Message::Halt(..) => self.process_halt(msg, transport),
+        }
+    }
+    pub fn initialize(&mut self, transport: &mut dyn Transport<W>) {
+        //println!("Initializing node {:?}..", self.index);
+        self.trace("init", String::new);
+        if self.adjacency.is_empty() {
+            /* An isolated node is a finished fragment on its own */
+            self.state = State::Found;
+            self.halted = true;
+            self.trace("state", String::new);
+            self.trace("halt", String::new);
+            self.publish();
+            return;
+        }
+        let nbr_q = self.adjacency[0].1;
+        /* Every edge starts out 'Status::Basic', then the least one becomes a branch */
+        self.status = vec![Status::Basic; self.adjacency.len()];
+        self.set_status(nbr_q, Status::Branch);
         self.level = 0;
         self.state = State::Found;
         self.rec = 0;
-        let sender = sender_mapping
-            .get(&nbr_q)
-            .expect("Error while reading 'sender_mapping':");
-        let msg = Message::Connect(0, self.index);
-        sender
-            .send(msg.clone())
-            .expect("Error while sending message:");
-        /*println!(
-            "Thread [{:?}]: Sent message {:?} to {:?}",
-            self.index, msg, nbr_q
-        );*/
-    }
-    pub fn process_connect(
-        &mut self,
-        msg: Message,
-        sender_mapping: &HashMap<NodeIndex, Sender<Message>>,
-    ) {
+        self.trace("state", String::new);
+        self.send(nbr_q, Message::Connect(0, self.index), transport);
+        self.publish();
+    }
+    pub fn process_connect(&mut self, msg: Message<W>, transport: &mut dyn Transport<W>) {
         if let Message::Connect(level, sender_index) = msg {
             if level < self.level {
-                self.status.insert(sender_index, Status::Branch);
-                let sender = sender_mapping
-                    .get(&sender_index)
-                    .expect("Error while reading 'sender_mapping':");
-                let msg = Message::Initiate(self.level, self.name, self.state, self.index);
-                sender
-                    .send(msg.clone())
-                    .expect("Error while sending message:");
-            /*println!(
-                "Thread [{:?}]: Sent message {:?} to {:?}",
-                self.index, msg, sender_index
-            );*/
-            } else if *self
-                .status
-                .get(&sender_index)
-                .expect("Error while reading 'status':")
-                == Status::Basic
-            {
+                self.set_status(sender_index, Status::Branch);
+                let name = self.name.expect("Error: name found 'None':");
+                let msg = Message::Initiate(self.level, name, self.state, self.index);
+                self.send(sender_index, msg, transport);
+            } else if self.status_to(sender_index) == Status::Basic {
                 // wait
-                /* Add the message to the end of the channel */
-                let sender = sender_mapping
-                    .get(&self.index)
-                    .expect("Error while reading 'sender_mapping':");
-                sender
-                    .send(msg.clone())
-                    .expect("Error while sending message:");
-            /*println!(
-                "Thread [{:?}]: Pushed message {:?} to the end of the channel",
-                self.index, msg
-            );*/
+                self.defer(msg);
             } else {
-                let sender = sender_mapping
-                    .get(&sender_index)
-                    .expect("Error while reading 'sender_mapping':");
-
-                /* Finds the weight of the edge between 'self.index' and 'sender_index' */
-                let mut wt = 0; // see if the initial value is correct
-                {
-                    let graph = self.graph.read().expect("Error while reading 'graph':");
-                    for edge in graph.edges(self.index) {
-                        let src = edge.source();
-                        let target = edge.target();
-                        let raw_edge = (self.index, sender_index);
-                        if (src, target) == raw_edge || (target, src) == raw_edge {
-                            // should get into at least once
-                            wt = *edge.weight();
-                            break;
-                        }
-                    }
-                }
-                let new_name = wt;
+                /* The new fragment is named after the core edge */
+                let new_name = self.edge_key_to(sender_index);
 
                 let msg = Message::Initiate(self.level + 1, new_name, State::Find, self.index);
-                sender
-                    .send(msg.clone())
-                    .expect("Error while sending message:");
-                /*println!(
-                    "Thread [{:?}]: Sent message {:?} to {:?}",
-                    self.index, msg, sender_index
-                );*/
+                self.send(sender_index, msg, transport);
             }
         } else {
             panic!("Wrong control flow!");
         }
     }
-    pub fn process_initiate(
-        &mut self,
-        msg: Message,
-        sender_mapping: &HashMap<NodeIndex, Sender<Message>>,
-    ) {
+    pub fn process_initiate(&mut self, msg: Message<W>, transport: &mut dyn Transport<W>) {
         if let Message::Initiate(level, name, state, sender_index) = msg {
             self.level = level;
-            self.name = name;
+            self.stats.max_level = self.stats.max_level.max(level);
+            self.name = Some(name);
             self.state = state;
             self.parent = Some(sender_index);
             self.best_node = None;
-            self.best_wt = std::i32::MAX;
+            self.best_wt = BestWeight::Infinity;
             self.test_node = None;
-            {
-                let graph = self.graph.read().expect("Error while reading 'graph':");
-                for nbr_index in graph.neighbors(self.index) {
-                    if nbr_index != sender_index
-                        && *self
-                            .status
-                            .get(&nbr_index)
-                            .expect("Error while reading 'status':")
-                            == Status::Branch
-                    {
-                        let sender = sender_mapping
-                            .get(&nbr_index)
-                            .expect("Error while reading 'sender_mapping':");
-                        let msg = Message::Initiate(level, name, state, self.index);
-                        sender
-                            .send(msg.clone())
-                            .expect("Error while sending message:");
-                        /*println!(
-                            "Thread [{:?}]: Sent message {:?} to {:?}",
-                            self.index, msg, nbr_index
-                        );*/
-                    }
-                }
+            self.trace("state", String::new);
+            for nbr_index in self.branches_except(sender_index) {
+                let msg = Message::Initiate(level, name, state, self.index);
+                self.send(nbr_index, msg, transport);
             }
             if state == State::Find {
                 self.rec = 0;
-                self.find_min(sender_mapping);
+                self.find_min(transport);
             }
         } else {
             panic!("Wrong control flow!");
         }
     }
-    pub fn find_min(&mut self, sender_mapping: &HashMap<NodeIndex, Sender<Message>>) {
-        let mut min_edge = None;
-        let mut q = None;
-        let mut wt = std::i32::MAX;
-        {
-            let graph = self.graph.read().expect("Error while reading 'graph':");
-            let edges = graph.edges(self.index);
-            for edge in edges {
-                let src = edge.source();
-                let target = edge.target();
-                let nbr_q = if src == self.index { target } else { src };
-                if *self
-                    .status
-                    .get(&nbr_q)
-                    .expect("Error while reading 'status':")
-                    == Status::Basic
-                    && (min_edge == None || *edge.weight() < wt)
-                {
-                    min_edge = Some(edge.id());
-                    q = Some(nbr_q);
-                    wt = *edge.weight();
-                }
+    pub fn find_min(&mut self, transport: &mut dyn Transport<W>) {
+        /* 'adjacency' is sorted, so the first 'Basic' edge is the least one */
+        while self.basic_from < self.adjacency.len() {
+            if self.status[self.basic_from] == Status::Basic {
+                break;
             }
+            self.basic_from += 1;
         }
-        if let Some(_edge) = min_edge {
-            if let Some(nbr_q) = q {
-                self.test_node = q;
-                let sender = sender_mapping
-                    .get(&nbr_q)
-                    .expect("Error while reading 'sender_mapping':");
-                let msg = Message::Test(self.level, self.name, self.index);
-                sender
-                    .send(msg.clone())
-                    .expect("Error while sending message:"); // check clone()
-                                                             /*println!(
-                                                                 "Thread [{:?}]: Sent message {:?} to {:?}",
-                                                                 self.index, msg, nbr_q
-                                                             );*/
-            } else {
-                //println!("Invalid control flow!");
-            }
+        if let Some(&(_, nbr_q)) = self.adjacency.get(self.basic_from) {
+            self.test_node = Some(nbr_q);
+            let name = self.name.expect("Error: name found 'None':");
+            let msg = Message::Test(self.level, name, self.index);
+            self.send(nbr_q, msg, transport);
         } else {
             self.test_node = None;
-            self.report(sender_mapping);
+            self.report(transport);
         }
     }
-    pub fn report(&mut self, sender_mapping: &HashMap<NodeIndex, Sender<Message>>) {
-        let graph = self.graph.read().expect("Error while reading 'graph':");
-        let mut cnt = 0;
-        for q in graph.node_indices() {
-            if *self.status.get(&q).expect("Error while reading 'status':") == Status::Branch
-                && q != self.parent.expect("Error: parent found 'None':")
-            {
-                cnt += 1;
-            }
-        }
-        if self.rec == cnt && self.test_node == None {
+    pub fn report(&mut self, transport: &mut dyn Transport<W>) {
+        let parent = self.parent.expect("Error: parent found 'None':");
+        /* Branch edges other than the one to 'parent' lead to children */
+        let cnt = self.branches - u32::from(self.status_to(parent) == Status::Branch);
+        if self.rec == cnt && self.test_node.is_none() {
             self.state = State::Found;
-            let sender = sender_mapping
-                .get(&self.parent.expect("Error: parent found 'None':"))
-                .expect("Error while reading 'sender_mapping':");
+            self.trace("state", String::new);
             let msg = Message::Report(self.best_wt, self.index);
-            sender
-                .send(msg.clone())
-                .expect("Error while sending message:");
-        /*println!(
-            "Thread [{:?}]: Sent message {:?} to {:?}",
-            self.index,
-            msg,
-            self.parent.unwrap()
-        );*/
+            self.send(parent, msg, transport);
         } else {
             //skip
         }
     }
-    pub fn process_test(
-        &mut self,
-        msg: Message,
-        sender_mapping: &HashMap<NodeIndex, Sender<Message>>,
-    ) {
+    pub fn process_test(&mut self, msg: Message<W>, transport: &mut dyn Transport<W>) {
         if let Message::Test(level, name, sender_index) = msg {
             if level > self.level {
                 /* wait */
-                /* Add the message to the end of the channel */
-                let sender = sender_mapping
-                    .get(&self.index)
-                    .expect("Error while reading 'sender_mapping':");
-                sender
-                    .send(msg.clone())
-                    .expect("Error while sending message:");
-            /*println!(
-                "Thread [{:?}]: Pushed message {:?} to the end of the channel",
-                self.index, msg
-            );*/
-            } else if self.name == name {
-                if *self
-                    .status
-                    .get(&sender_index)
-                    .expect("Error while reading 'status':")
-                    == Status::Basic
-                {
-                    self.status.insert(sender_index, Status::Reject);
+                self.defer(msg);
+            } else if self.name == Some(name) {
+                if self.status_to(sender_index) == Status::Basic {
+                    self.set_status(sender_index, Status::Reject);
                 }
                 /* Doing additional check : if self.test_node is 'None'  */
                 /* Modification of the original algorithm */
-                if self.test_node == None
+                if self.test_node.is_none()
                     || sender_index != self.test_node.expect("Error: test_node found 'None':")
                 {
-                    let sender = sender_mapping
-                        .get(&sender_index)
-                        .expect("Error while reading 'sender_mapping':");
-                    let msg = Message::Reject(self.index);
-                    sender
-                        .send(msg.clone())
-                        .expect("Error while sending message:");
-                /*println!(
-                    "Thread [{:?}]: Sent message {:?} to {:?}",
-                    self.index, msg, sender_index
-                );*/
+                    self.send(sender_index, Message::Reject(self.index), transport);
                 } else {
-                    self.find_min(sender_mapping);
+                    self.find_min(transport);
                 }
             } else {
-                let sender = sender_mapping
-                    .get(&sender_index)
-                    .expect("Error while reading 'sender_mapping':");
-                let msg = Message::Accept(self.index);
-                sender
-                    .send(msg.clone())
-                    .expect("Error while sending message:");
-                /*println!(
-                    "Thread [{:?}]: Sent message {:?} to {:?}",
-                    self.index, msg, sender_index
-                );*/
+                self.send(sender_index, Message::Accept(self.index), transport);
             }
         } else {
             panic!("Wrong control flow!");
         }
     }
-    pub fn process_accept(
-        &mut self,
-        msg: Message,
-        sender_mapping: &HashMap<NodeIndex, Sender<Message>>,
-    ) {
+    pub fn process_accept(&mut self, msg: Message<W>, transport: &mut dyn Transport<W>) {
         if let Message::Accept(sender_index) = msg {
             self.test_node = None;
-            /* Finds the weight of the edge between 'self.index' and 'sender_index' */
-            let mut wt = 0; // see if the initial value is correct
-            {
-                let graph = self.graph.read().expect("Error while reading 'graph':");
-                for edge in graph.edges(self.index) {
-                    let src = edge.source();
-                    let target = edge.target();
-                    let raw_edge = (self.index, sender_index);
-                    if (src, target) == raw_edge || (target, src) == raw_edge {
-                        // should get into at least once
-                        wt = *edge.weight();
-                        break;
-                    }
-                }
-            }
+            let wt = BestWeight::Edge(self.edge_key_to(sender_index));
             if wt < self.best_wt {
                 self.best_wt = wt;
                 self.best_node = Some(sender_index);
             }
-            self.report(sender_mapping);
+            self.report(transport);
         } else {
             panic!("Wrong control flow!");
         }
     }
-    pub fn process_reject(
-        &mut self,
-        msg: Message,
-        sender_mapping: &HashMap<NodeIndex, Sender<Message>>,
-    ) {
+    pub fn process_reject(&mut self, msg: Message<W>, transport: &mut dyn Transport<W>) {
         if let Message::Reject(sender_index) = msg {
-            if *self
-                .status
-                .get(&sender_index)
-                .expect("Error while reading 'status':")
-                == Status::Basic
-            {
-                self.status.insert(sender_index, Status::Reject);
+            if self.status_to(sender_index) == Status::Basic {
+                self.set_status(sender_index, Status::Reject);
             }
-            self.find_min(sender_mapping);
+            self.find_min(transport);
         } else {
             panic!("Wrong control flow!");
         }
     }
-    pub fn process_report(
-        &mut self,
-        msg: Message,
-        sender_mapping: &HashMap<NodeIndex, Sender<Message>>,
-    ) {
+    pub fn process_report(&mut self, msg: Message<W>, transport: &mut dyn Transport<W>) {
         if let Message::Report(wt, sender_index) = msg {
             if sender_index != self.parent.expect("Error: parent found 'None':") {
                 if wt < self.best_wt {
@@ -417,26 +553,16 @@ impl Node {
                     self.best_node = Some(sender_index);
                 }
                 self.rec += 1;
-                self.report(sender_mapping);
+                self.report(transport);
             } else if self.state == State::Find {
                 /* wait */
-                /* Add the message to the end of the channel */
-                let sender = sender_mapping
-                    .get(&self.index)
-                    .expect("Error while reading 'sender_mapping':");
-                sender
-                    .send(msg.clone())
-                    .expect("Error while sending message:");
-            /*println!(
-                "Thread [{:?}]: Pushed message {:?} to the end of the channel",
-                self.index, msg
-            );*/
+                self.defer(msg);
             } else if wt > self.best_wt {
-                self.change_root(sender_mapping);
-            } else if wt == self.best_wt && wt == std::i32::MAX {
-                /* stop */
-                let mut stop = self.stop.write().unwrap();
-                *stop.get_mut() = true;
+                self.change_root(transport);
+            } else if wt == BestWeight::Infinity && self.best_wt == BestWeight::Infinity {
+                /* stop: both core nodes get here, each one halts its own half of the tree */
+                let parent = self.parent.expect("Error: parent found 'None':");
+                self.halt(parent, transport);
             } else {
                 //invalid
             }
@@ -444,56 +570,45 @@ impl Node {
             panic!("Wrong control flow!");
         }
     }
-    pub fn change_root(&mut self, sender_mapping: &HashMap<NodeIndex, Sender<Message>>) {
-        if *self
-            .status
-            .get(&self.best_node.expect("Error: best_node found 'None':"))
-            .expect("Error while reading 'status':")
-            == Status::Branch
-        {
-            let sender = sender_mapping
-                .get(&self.best_node.expect("Error: best_node found 'None':"))
-                .expect("Error while reading 'sender_mapping':");
-            let msg = Message::ChangeRoot(self.index);
-            sender
-                .send(msg.clone())
-                .expect("Error while sending message:");
-        /*println!(
-            "Thread [{:?}]: Sent message {:?} to {:?}",
-            self.index,
-            msg,
-            self.best_node.unwrap()
-        );*/
+    pub fn change_root(&mut self, transport: &mut dyn Transport<W>) {
+        let best_node = self.best_node.expect("Error: best_node found 'None':");
+        if self.status_to(best_node) == Status::Branch {
+            self.send(best_node, Message::ChangeRoot(self.index), transport);
         } else {
             /* Whether to insert the status 'before' or 'after' the message is sent? */
-            self.status.insert(
-                self.best_node.expect("Error: best_node found 'None':"),
-                Status::Branch,
-            );
-            let sender = sender_mapping
-                .get(&self.best_node.expect("Error: best_node found 'None':"))
-                .expect("Error while reading 'sender_mapping':");
+            self.set_status(best_node, Status::Branch);
             let msg = Message::Connect(self.level, self.index);
-            sender
-                .send(msg.clone())
-                .expect("Error while sending message:");
-            /*println!(
-                "Thread [{:?}]: Sent message {:?} to {:?}",
-                self.index,
-                msg,
-                self.best_node.unwrap()
-            );*/
+            self.send(best_node, msg, transport);
         }
     }
-    pub fn process_change_root(
-        &mut self,
-        msg: Message,
-        sender_mapping: &HashMap<NodeIndex, Sender<Message>>,
-    ) {
+    pub fn process_change_root(&mut self, msg: Message<W>, transport: &mut dyn Transport<W>) {
         if let Message::ChangeRoot(_sender_index) = msg {
-            self.change_root(sender_mapping);
+            self.change_root(transport);
+        } else {
+            panic!("Wrong control flow!");
+        }
+    }
+    pub fn process_halt(&mut self, msg: Message<W>, transport: &mut dyn Transport<W>) {
+        if let Message::Halt(sender_index) = msg {
+            self.halt(sender_index, transport);
         } else {
             panic!("Wrong control flow!");
         }
     }
+    /// Marks this node halted and forwards `Halt` on every branch except the one towards `from`.
+    fn halt(&mut self, from: NodeIndex, transport: &mut dyn Transport<W>) {
+        self.halted = true;
+        self.trace("halt", String::new);
+        for nbr_index in self.branches_except(from) {
+            self.send(nbr_index, Message::Halt(self.index), transport);
+        }
+    }
+    /// Neighbours connected by a `Status::Branch` edge, other than `except`.
+    fn branches_except(&self, except: NodeIndex) -> Vec<NodeIndex> {
+        self.neighbours()
+            .zip(&self.status)
+            .filter(|&(nbr_index, status)| nbr_index != except && *status == Status::Branch)
+            .map(|(nbr_index, _)| nbr_index)
+            .collect()
+    }
 }
@@ -0,0 +1,133 @@
+use crate::dot;
+use crate::mst::MstEdge;
+use crate::weight::Weight;
+use crate::Run;
+use petgraph::graph::Graph;
+use petgraph::Undirected;
+use std::fmt;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// How the result of a run is written out.
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub enum OutputFormat {
+    /// `(u, v, w)` lines, with a `# component` header per component of a forest.
+    #[default]
+    Tuple,
+    /// One JSON object with the node, edge and component counts, the total
+    /// weight and the edges sorted by weight.
+    Json,
+    /// A `source,target,weight` header and one line per edge, sorted by weight.
+    Csv,
+    /// The input graph in Graphviz DOT with the tree edges highlighted.
+    Dot,
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OutputFormat::Tuple => write!(f, "tuple"),
+            OutputFormat::Json => write!(f, "json"),
+            OutputFormat::Csv => write!(f, "csv"),
+            OutputFormat::Dot => write!(f, "dot"),
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tuple" => Ok(OutputFormat::Tuple),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "dot" => Ok(OutputFormat::Dot),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Renders the forest computed by `run` on `graph` as `format`.
+pub fn format_mst<N, W: Weight>(
+    format: OutputFormat,
+    graph: &Graph<N, W, Undirected>,
+    run: &Run<W>,
+) -> String {
+    let mst = &run.mst;
+    let mut out = String::new();
+    match format {
+        OutputFormat::Tuple => {
+            /* A forest is printed component by component, each under a comment header */
+            let forest = mst.components.len() > 1;
+            for (id, component) in mst.components.iter().enumerate() {
+                if forest {
+                    writeln!(out, "# component {} ({} nodes)", id, component.nodes.len())
+                        .expect("Error while writing to a String:");
+                }
+                for edge in &component.edges {
+                    writeln!(
+                        out,
+                        "({}, {}, {})",
+                        edge.source.index(),
+                        edge.target.index(),
+                        edge.weight
+                    )
+                    .expect("Error while writing to a String:");
+                }
+            }
+        }
+        OutputFormat::Json => {
+            let edges: Vec<String> = mst.edges.iter().map(edge_json).collect();
+            writeln!(
+                out,
+                "{{\"node_count\":{},\"edge_count\":{},\"component_count\":{},\"total_weight\":{},\
+                 \"edges\":[{}]}}",
+                graph.node_count(),
+                mst.edges.len(),
+                mst.components.len(),
+                json_number(&mst.total_weight().to_string()),
+                edges.join(",")
+            )
+            .expect("Error while writing to a String:");
+        }
+        OutputFormat::Csv => {
+            out.push_str("source,target,weight\n");
+            for edge in &mst.edges {
+                writeln!(
+                    out,
+                    "{},{},{}",
+                    edge.source.index(),
+                    edge.target.index(),
+                    edge.weight
+                )
+                .expect("Error while writing to a String:");
+            }
+        }
+        OutputFormat::Dot => out = dot::to_dot(graph, &run.nodes, false),
+    }
+    out
+}
+
+fn edge_json<W: Weight>(edge: &MstEdge<W>) -> String {
+    format!(
+        "{{\"source\":{},\"target\":{},\"weight\":{}}}",
+        edge.source.index(),
+        edge.target.index(),
+        json_number(&edge.weight.to_string())
+    )
+}
+
+/// `text` as a JSON number, or as a string when it is not one (e.g. `inf`).
+fn json_number(text: &str) -> String {
+    let digits = text.strip_prefix('-').unwrap_or(text);
+    let is_number = digits.starts_with(|ch: char| ch.is_ascii_digit())
+        && digits
+            .chars()
+            .all(|ch| ch.is_ascii_digit() || matches!(ch, '.' | 'e' | 'E' | '+' | '-'));
+    if is_number {
+        text.to_string()
+    } else {
+        format!("\"{}\"", text)
+    }
+}
@@ -0,0 +1,188 @@
+use crate::node::{Message, Node};
+use crate::runner::{build_node, collect, Outcome};
+use crate::snapshot::SnapshotRecorder;
+use crate::transport::{Inbox, Transport};
+use crate::weight::Weight;
+use crate::Options;
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::Undirected;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+/// Messages a node handles before it goes back to the end of the ready queue,
+/// so that a busy node cannot hold on to a worker.
+const BATCH: usize = 64;
+
+/// A node of the pool and whether it has woken up yet.
+struct Slot<W> {
+    node: Node<W>,
+    started: bool,
+}
+
+/// Nodes waiting for a worker, and whether the run is over.
+struct Ready {
+    queue: VecDeque<usize>,
+    done: bool,
+}
+
+/// State shared by the workers of `run_pool`.
+struct Pool<W> {
+    slots: Vec<Mutex<Slot<W>>>,
+    inboxes: Vec<Inbox<W>>,
+    /// Whether a node is in the ready queue or being run by a worker; a node
+    /// is queued only when this goes from false to true.
+    scheduled: Vec<AtomicBool>,
+    ready: Mutex<Ready>,
+    wake: Condvar,
+    halted: AtomicUsize,
+}
+
+impl<W> Pool<W> {
+    /// Queues `node` unless it is already queued or running.
+    fn schedule(&self, node: usize) {
+        if !self.scheduled[node].swap(true, Ordering::SeqCst) {
+            self.requeue(node);
+        }
+    }
+
+    /// Puts `node`, which must be marked scheduled, at the end of the ready queue.
+    fn requeue(&self, node: usize) {
+        let mut ready = self.ready.lock().expect("Error while locking 'ready':");
+        ready.queue.push_back(node);
+        self.wake.notify_one();
+    }
+
+    /// Next node to run, or `None` once every node has halted.
+    fn next(&self) -> Option<usize> {
+        let mut ready = self.ready.lock().expect("Error while locking 'ready':");
+        loop {
+            if ready.done {
+                return None;
+            }
+            if let Some(node) = ready.queue.pop_front() {
+                return Some(node);
+            }
+            ready = self
+                .wake
+                .wait(ready)
+                .expect("Error while waiting on 'wake':");
+        }
+    }
+
+    fn finish(&self) {
+        self.ready
+            .lock()
+            .expect("Error while locking 'ready':")
+            .done = true;
+        self.wake.notify_all();
+    }
+}
+
+/// Sending side of the pool: delivers into the inbox and schedules the receiver.
+impl<W> Transport<W> for &Pool<W> {
+    fn send(&mut self, from: NodeIndex, to: NodeIndex, msg: Message<W>) {
+        self.inboxes[to.index()].push(from, msg);
+        self.schedule(to.index());
+    }
+}
+
+/// Runs GHS with every node multiplexed over a pool of `threads` workers.
+///
+/// A node is handed to a worker only when it has messages to handle (or has
+/// not woken up yet), so the number of nodes is not limited by the number of
+/// threads the system allows.
+pub fn run_pool<N, W: Weight>(
+    graph: &Graph<N, W, Undirected>,
+    options: &Options,
+    threads: usize,
+    recorder: Option<&Arc<SnapshotRecorder<W>>>,
+) -> Outcome<W> {
+    let (slots, inboxes): (Vec<_>, Vec<_>) = graph
+        .node_indices()
+        .map(|node_index| {
+            let node = build_node(graph, node_index, options, recorder);
+            let inbox = Inbox::new(node.neighbours().collect());
+            (
+                Mutex::new(Slot {
+                    node,
+                    started: false,
+                }),
+                inbox,
+            )
+        })
+        .unzip();
+    let count = slots.len();
+    /* Every node starts out queued, to wake up */
+    let pool = Pool {
+        slots,
+        inboxes,
+        scheduled: (0..count).map(|_| AtomicBool::new(true)).collect(),
+        ready: Mutex::new(Ready {
+            queue: (0..count).collect(),
+            done: count == 0,
+        }),
+        wake: Condvar::new(),
+        halted: AtomicUsize::new(0),
+    };
+
+    thread::scope(|scope| {
+        for worker in 0..threads.clamp(1, count.max(1)) {
+            let pool = &pool;
+            thread::Builder::new()
+                .name(format!("worker {}", worker))
+                .spawn_scoped(scope, move || work(pool))
+                .expect("Error while spawning a worker:");
+        }
+    });
+
+    let nodes: Vec<Node<W>> = pool
+        .slots
+        .into_iter()
+        .map(|slot| {
+            slot.into_inner()
+                .expect("Error while unwrapping 'slot':")
+                .node
+        })
+        .collect();
+    collect(&nodes)
+}
+
+/// Runs queued nodes until every node has halted.
+fn work<W: Weight>(pool: &Pool<W>) {
+    let mut transport = pool;
+    while let Some(id) = pool.next() {
+        let mut slot = pool.slots[id].lock().expect("Error while locking 'slot':");
+        let was_halted = slot.node.is_halted();
+        if !slot.started {
+            slot.started = true;
+            slot.node.initialize(&mut transport);
+        }
+        let mut handled = 0;
+        /* A halted node stays marked scheduled, so it is never queued again */
+        while !slot.node.is_halted() && handled < BATCH {
+            match pool.inboxes[id].try_recv() {
+                Some(msg) => slot.node.process(msg, &mut transport),
+                None => break,
+            }
+            handled += 1;
+        }
+        let halted = slot.node.is_halted();
+        drop(slot);
+
+        if halted {
+            if !was_halted && pool.halted.fetch_add(1, Ordering::SeqCst) + 1 == pool.slots.len() {
+                pool.finish();
+            }
+        } else if handled == BATCH {
+            pool.requeue(id);
+        } else {
+            /* A message sent after the inbox was found empty must not be missed */
+            pool.scheduled[id].store(false, Ordering::SeqCst);
+            if pool.inboxes[id].has_messages() {
+                pool.schedule(id);
+            }
+        }
+    }
+}
@@ -0,0 +1,414 @@
+use crate::json;
+use crate::json::Value;
+use crate::node::{EdgeKey, Message, Node, State};
+use crate::trace;
+use crate::transport::Transport;
+use crate::weight::Weight;
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::Undirected;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::fmt::Debug;
+use std::str::FromStr;
+
+/// What a node did in one replay step.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Action<W> {
+    /// The node woke up and ran `initialize`.
+    Init,
+    /// The node took `Message` from the network and ran `process`.
+    Receive(Message<W>),
+}
+
+/// One recorded `init` or `receive` event, with the node's recorded state before it.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Step<W> {
+    /// 1-based line of the event in the trace.
+    pub line: usize,
+    pub node: NodeIndex,
+    pub action: Action<W>,
+    pub level: u32,
+    pub name: Option<EdgeKey<W>>,
+    pub state: State,
+}
+
+/// Why a trace could not be read or replayed.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum ReplayErrorKind<W> {
+    /// The line is not valid JSON.
+    InvalidJson,
+    /// The first line is not a `graph` event.
+    MissingHeader,
+    /// An event lacks a field or has a field of the wrong type.
+    InvalidEvent,
+    /// The trace was recorded with a different weight type.
+    WrongWeights { found: String },
+    /// Before the step, the replayed node is not in the recorded level, name and state.
+    StateMismatch {
+        node: NodeIndex,
+        recorded: (u32, Option<EdgeKey<W>>, State),
+        replayed: (u32, Option<EdgeKey<W>>, State),
+    },
+    /// The replayed node sent something else than the trace records; `None`
+    /// on one side means that side sent fewer messages.
+    Diverged {
+        node: NodeIndex,
+        recorded: Option<(NodeIndex, Message<W>)>,
+        replayed: Option<(NodeIndex, Message<W>)>,
+    },
+}
+
+/// A replay failure, located by the 1-based line of the trace event.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ReplayError<W> {
+    pub line: usize,
+    pub kind: ReplayErrorKind<W>,
+}
+
+impl<W: fmt::Display> fmt::Display for ReplayError<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", self.line)?;
+        match &self.kind {
+            ReplayErrorKind::InvalidJson => write!(f, "not a JSON object"),
+            ReplayErrorKind::MissingHeader => write!(f, "trace does not start with a graph"),
+            ReplayErrorKind::InvalidEvent => write!(f, "malformed event"),
+            ReplayErrorKind::WrongWeights { found } => {
+                write!(f, "trace was recorded with '{}' weights", found)
+            }
+            ReplayErrorKind::StateMismatch {
+                node,
+                recorded,
+                replayed,
+            } => write!(
+                f,
+                "node {} is {}, the trace has it {}",
+                node.index(),
+                Snapshot(replayed),
+                Snapshot(recorded)
+            ),
+            ReplayErrorKind::Diverged {
+                node,
+                recorded,
+                replayed,
+            } => match (recorded, replayed) {
+                (Some((recorded_to, recorded)), Some((to, msg))) => write!(
+                    f,
+                    "node {} sent {} to {}, the trace has {} to {}",
+                    node.index(),
+                    msg,
+                    to.index(),
+                    recorded,
+                    recorded_to.index()
+                ),
+                (Some((to, msg)), None) => write!(
+                    f,
+                    "node {} did not send {} to {}",
+                    node.index(),
+                    msg,
+                    to.index()
+                ),
+                (None, Some((to, msg))) => write!(
+                    f,
+                    "node {} sent {} to {}, which the trace does not have",
+                    node.index(),
+                    msg,
+                    to.index()
+                ),
+                (None, None) => write!(f, "node {} diverged", node.index()),
+            },
+        }
+    }
+}
+
+/// Formats a `(level, name, state)` triple for `ReplayError`.
+struct Snapshot<'a, W>(&'a (u32, Option<EdgeKey<W>>, State));
+
+impl<W: fmt::Display> fmt::Display for Snapshot<'_, W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (level, name, state) = self.0;
+        write!(f, "{:?} at level {}", state, level)?;
+        match name {
+            Some(name) => write!(f, " in fragment {}", name),
+            None => write!(f, " without a fragment"),
+        }
+    }
+}
+
+impl<W: Debug + fmt::Display> Error for ReplayError<W> {}
+
+/// Weight type named in the header of a trace, if the header can be read.
+pub fn trace_weights(text: &str) -> Option<String> {
+    let header = json::parse(text.lines().next()?)?;
+    header
+        .get("weights")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+/// Transport that keeps what a node sends during one step.
+struct Outbox<W> {
+    sent: Vec<(NodeIndex, Message<W>)>,
+}
+
+impl<W> Transport<W> for Outbox<W> {
+    fn send(&mut self, _from: NodeIndex, to: NodeIndex, msg: Message<W>) {
+        self.sent.push((to, msg));
+    }
+}
+
+/// Re-executes a recorded run one `init` or `receive` event at a time.
+///
+/// Every node is rebuilt from the graph in the trace header and fed the
+/// recorded events in `seq` order. After each step the messages the node sent
+/// are checked against the `send` events recorded for it, so the first handler
+/// that behaves differently from the recorded run is reported.
+///
+/// A trace without the final `end` event comes from a run that did not finish;
+/// the last step of each node may then have been cut short, and sends missing
+/// from it are not reported.
+pub struct Replay<W> {
+    graph: Graph<i32, W, Undirected>,
+    nodes: Vec<Node<W>>,
+    steps: Vec<Step<W>>,
+    /// For each step, the sends recorded while the node handled it, with their lines.
+    sends: Vec<Vec<(usize, NodeIndex, Message<W>)>>,
+    /// For each step, whether it is the last one of its node.
+    last: Vec<bool>,
+    /// Whether the trace has its `end` event.
+    complete: bool,
+    next: usize,
+}
+
+impl<W: Weight + FromStr> Replay<W> {
+    /// Reads a trace written by `Tracer`.
+    pub fn from_trace(text: &str) -> Result<Self, ReplayError<W>> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(line_no, line)| (line_no + 1, line))
+            .filter(|(_, line)| !line.trim().is_empty());
+        let error = |line, kind| ReplayError { line, kind };
+
+        let (line_no, line) = lines
+            .next()
+            .ok_or(error(1, ReplayErrorKind::MissingHeader))?;
+        let header = json::parse(line).ok_or(error(line_no, ReplayErrorKind::InvalidJson))?;
+        if header.get("event").and_then(Value::as_str) != Some("graph") {
+            return Err(error(line_no, ReplayErrorKind::MissingHeader));
+        }
+        let graph = graph_from_header::<W>(&header).map_err(|kind| error(line_no, kind))?;
+        let tie_break = header
+            .get("tie_break")
+            .and_then(Value::as_bool)
+            .ok_or(error(line_no, ReplayErrorKind::InvalidEvent))?;
+
+        let mut steps = vec![];
+        let mut sends: Vec<Vec<_>> = vec![];
+        /* A node's sends belong to the latest step of that node */
+        let mut last_step: HashMap<NodeIndex, usize> = HashMap::new();
+        let mut complete = false;
+        for (line_no, line) in lines {
+            let event = json::parse(line).ok_or(error(line_no, ReplayErrorKind::InvalidJson))?;
+            let invalid = || error(line_no, ReplayErrorKind::InvalidEvent);
+            let kind = event
+                .get("event")
+                .and_then(Value::as_str)
+                .ok_or_else(invalid)?;
+            if kind == "end" {
+                complete = true;
+                continue;
+            }
+            let node = event
+                .get("node")
+                .and_then(trace::node_from_json)
+                .filter(|node| node.index() < graph.node_count())
+                .ok_or_else(invalid)?;
+            let action = match kind {
+                "init" => Action::Init,
+                "receive" => Action::Receive(
+                    event
+                        .get("msg")
+                        .and_then(trace::message_from_json)
+                        .ok_or_else(invalid)?,
+                ),
+                "send" => {
+                    let to = event
+                        .get("to")
+                        .and_then(trace::node_from_json)
+                        .ok_or_else(invalid)?;
+                    let msg = event
+                        .get("msg")
+                        .and_then(trace::message_from_json)
+                        .ok_or_else(invalid)?;
+                    let step = *last_step.get(&node).ok_or_else(invalid)?;
+                    sends[step].push((line_no, to, msg));
+                    continue;
+                }
+                /* the rest follows from replaying 'init' and 'receive' */
+                _ => continue,
+            };
+            last_step.insert(node, steps.len());
+            sends.push(vec![]);
+            steps.push(Step {
+                line: line_no,
+                node,
+                action,
+                level: event
+                    .get("level")
+                    .and_then(Value::as_u64)
+                    .and_then(|level| u32::try_from(level).ok())
+                    .ok_or_else(invalid)?,
+                name: event
+                    .get("name")
+                    .and_then(trace::name_from_json)
+                    .ok_or_else(invalid)?,
+                state: event
+                    .get("state")
+                    .and_then(trace::state_from_json)
+                    .ok_or_else(invalid)?,
+            });
+        }
+
+        let mut last = vec![false; steps.len()];
+        for step in last_step.into_values() {
+            last[step] = true;
+        }
+        let nodes = graph
+            .node_indices()
+            .map(|node_index| Node::new(&graph, node_index, tie_break))
+            .collect();
+        Ok(Replay {
+            graph,
+            nodes,
+            steps,
+            sends,
+            last,
+            complete,
+            next: 0,
+        })
+    }
+
+    /// Replays the next step; `Ok(None)` once every step has been replayed.
+    pub fn step(&mut self) -> Result<Option<&Step<W>>, ReplayError<W>> {
+        let Some(step) = self.steps.get(self.next) else {
+            return Ok(None);
+        };
+        let node = &mut self.nodes[step.node.index()];
+        let replayed = (node.level(), node.name(), node.state());
+        let recorded = (step.level, step.name, step.state);
+        if replayed != recorded {
+            return Err(ReplayError {
+                line: step.line,
+                kind: ReplayErrorKind::StateMismatch {
+                    node: step.node,
+                    recorded,
+                    replayed,
+                },
+            });
+        }
+
+        let mut outbox = Outbox { sent: vec![] };
+        match &step.action {
+            Action::Init => node.initialize(&mut outbox),
+            Action::Receive(msg) => node.process(msg.clone(), &mut outbox),
+        }
+        let mut recorded = self.sends[self.next].iter().cloned();
+        let mut replayed = outbox.sent.into_iter();
+        loop {
+            match (recorded.next(), replayed.next()) {
+                (None, None) => break,
+                /* the trace ends while the node handles its last step */
+                (None, Some(_)) if !self.complete && self.last[self.next] => break,
+                (Some((_, recorded_to, recorded_msg)), Some((to, msg)))
+                    if recorded_to == to && recorded_msg == msg => {}
+                (recorded, replayed) => {
+                    return Err(ReplayError {
+                        line: recorded.as_ref().map_or(step.line, |(line, _, _)| *line),
+                        kind: ReplayErrorKind::Diverged {
+                            node: step.node,
+                            recorded: recorded.map(|(_, to, msg)| (to, msg)),
+                            replayed,
+                        },
+                    });
+                }
+            }
+        }
+        self.next += 1;
+        Ok(self.steps.get(self.next - 1))
+    }
+
+    /// All recorded steps, in replay order.
+    pub fn steps(&self) -> &[Step<W>] {
+        &self.steps
+    }
+
+    /// Whether the trace comes from a run that finished.
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Number of steps replayed so far.
+    pub fn position(&self) -> usize {
+        self.next
+    }
+
+    /// Replayed nodes, indexed by node index.
+    pub fn nodes(&self) -> &[Node<W>] {
+        &self.nodes
+    }
+
+    /// Neighbours of `node` in the traced graph, in ascending order.
+    pub fn neighbours(&self, node: NodeIndex) -> Vec<NodeIndex> {
+        let mut neighbours: Vec<NodeIndex> = self.graph.neighbors(node).collect();
+        neighbours.sort();
+        neighbours
+    }
+}
+
+/// Rebuilds the traced graph from the trace header.
+fn graph_from_header<W: Weight + FromStr>(
+    header: &Value,
+) -> Result<Graph<i32, W, Undirected>, ReplayErrorKind<W>> {
+    let weights = header
+        .get("weights")
+        .and_then(Value::as_str)
+        .ok_or(ReplayErrorKind::InvalidEvent)?;
+    if weights != W::NAME {
+        return Err(ReplayErrorKind::WrongWeights {
+            found: weights.to_string(),
+        });
+    }
+    let nodes = header
+        .get("nodes")
+        .and_then(Value::as_u64)
+        .ok_or(ReplayErrorKind::InvalidEvent)?;
+    let mut graph: Graph<i32, W, Undirected> = Graph::default();
+    for _ in 0..nodes {
+        graph.add_node(i32::default());
+    }
+    let edges = header
+        .get("edges")
+        .and_then(Value::as_array)
+        .ok_or(ReplayErrorKind::InvalidEvent)?;
+    for edge in edges {
+        let (source, target, weight) = match edge.as_array() {
+            Some([source, target, weight]) => (
+                trace::node_from_json(source),
+                trace::node_from_json(target),
+                weight.as_str().and_then(|weight| W::from_str(weight).ok()),
+            ),
+            _ => return Err(ReplayErrorKind::InvalidEvent),
+        };
+        match (source, target, weight) {
+            (Some(source), Some(target), Some(weight))
+                if source.index() < graph.node_count() && target.index() < graph.node_count() =>
+            {
+                graph.add_edge(source, target, weight);
+            }
+            _ => return Err(ReplayErrorKind::InvalidEvent),
+        }
+    }
+    Ok(graph)
+}
@@ -0,0 +1,97 @@
+use crate::mst::MstEdge;
+use crate::node::{Node, NodeSnapshot};
+use crate::snapshot::SnapshotRecorder;
+use crate::stats::Stats;
+use crate::transport::Inbox;
+use crate::weight::Weight;
+use crate::Options;
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::Undirected;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::thread;
+
+/// Every node's final snapshot, the `Branch` edges of every node and the
+/// merged per-node statistics: what each backend returns.
+pub type Outcome<W> = (HashMap<NodeIndex, NodeSnapshot<W>>, Vec<MstEdge<W>>, Stats);
+
+/// Node `index` of `graph`, reporting to the tracer of `options` and to `recorder`.
+pub(crate) fn build_node<N, W: Weight>(
+    graph: &Graph<N, W, Undirected>,
+    index: NodeIndex,
+    options: &Options,
+    recorder: Option<&Arc<SnapshotRecorder<W>>>,
+) -> Node<W> {
+    let mut node = Node::new(graph, index, options.tie_break);
+    if let Some(tracer) = &options.trace {
+        node.set_tracer(Arc::clone(tracer));
+    }
+    if let Some(recorder) = recorder {
+        node.set_recorder(Arc::clone(recorder));
+    }
+    node
+}
+
+/// Gathers the outcome of a run from its nodes.
+pub(crate) fn collect<'a, W: Weight>(nodes: impl IntoIterator<Item = &'a Node<W>>) -> Outcome<W> {
+    let mut data = HashMap::new();
+    let mut branches = vec![];
+    let mut stats = Stats::default();
+    for node in nodes {
+        stats.merge(node.stats());
+        branches.extend(node.branch_edges());
+        data.insert(node.index(), node.snapshot());
+    }
+    (data, branches, stats)
+}
+
+/// Runs GHS with one thread per node.
+///
+/// Each thread owns its `Node` until the node halts.
+pub fn run_threads<N, W: Weight>(
+    graph: &Graph<N, W, Undirected>,
+    options: &Options,
+    recorder: Option<&Arc<SnapshotRecorder<W>>>,
+) -> Outcome<W> {
+    let nodes: Vec<Node<W>> = graph
+        .node_indices()
+        .map(|node_index| build_node(graph, node_index, options, recorder))
+        .collect();
+
+    /* One inbox per node, holding a FIFO queue for each incoming link */
+    let inboxes: Arc<HashMap<NodeIndex, Inbox<W>>> = Arc::new(
+        nodes
+            .iter()
+            .map(|node| (node.index(), Inbox::new(node.neighbours().collect())))
+            .collect(),
+    );
+
+    let mut handles = vec![];
+    for mut node in nodes {
+        let node_index = node.index();
+        let mut inboxes = Arc::clone(&inboxes);
+        let handle = thread::Builder::new()
+            .name(node_index.index().to_string())
+            .spawn(move || {
+                /* Should we wakeup (initialize) all the nodes? */
+                node.initialize(&mut inboxes);
+                /* Block until a message arrives; the node exits once the halt broadcast reaches it */
+                while !node.is_halted() {
+                    let msg = inboxes[&node_index].recv();
+                    node.process(msg, &mut inboxes);
+                }
+                node
+            });
+        handles.push(handle);
+    }
+    let nodes: Vec<Node<W>> = handles
+        .into_iter()
+        .map(|handle| {
+            handle
+                .expect("Error while unwrapping 'handle':")
+                .join()
+                .expect("Error while unwrapping 'handle.join()':")
+        })
+        .collect();
+    collect(&nodes)
+}
@@ -0,0 +1,154 @@
+use crate::node::{Message, Node};
+use crate::rng::Rng;
+use crate::runner::{build_node, collect, Outcome};
+use crate::snapshot::SnapshotRecorder;
+use crate::transport::Transport;
+use crate::weight::Weight;
+use crate::Options;
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::Undirected;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+/// A link with queued messages, as offered to a `Scheduler`.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct Link {
+    pub from: NodeIndex,
+    pub to: NodeIndex,
+    /// Number of messages sent in the whole run before the newest message on this link.
+    pub last_sent: u64,
+}
+
+/// Decides the order in which the simulated network delivers messages.
+///
+/// Only the choice of link is left to the scheduler: each link delivers its
+/// messages in the order they were sent, as GHS requires.
+pub trait Scheduler {
+    /// Index into `pending` of the link whose oldest message is delivered
+    /// next, or `None` to end the run with the remaining messages undelivered.
+    /// `pending` is never empty.
+    fn pick(&mut self, pending: &[Link]) -> Option<usize>;
+}
+
+/// Delivers from a uniformly random link; the scheduler of `Backend::Simulated`.
+#[derive(Clone, Debug)]
+pub struct RandomScheduler {
+    rng: Rng,
+}
+
+impl RandomScheduler {
+    pub fn new(seed: u64) -> Self {
+        RandomScheduler {
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl Scheduler for RandomScheduler {
+    fn pick(&mut self, pending: &[Link]) -> Option<usize> {
+        Some(self.rng.below(pending.len()))
+    }
+}
+
+/// Simulated network: one FIFO queue per directed link.
+///
+/// The scheduler picks which non-empty link delivers next, so messages on
+/// different links interleave freely while each link stays in order, as GHS
+/// requires.
+struct Network<W> {
+    queues: HashMap<(NodeIndex, NodeIndex), VecDeque<Message<W>>>,
+    /// Links with at least one queued message, in a deterministic order.
+    pending: Vec<Link>,
+    /// Position of every pending link in `pending`.
+    slots: HashMap<(NodeIndex, NodeIndex), usize>,
+    sent: u64,
+}
+
+impl<W> Transport<W> for Network<W> {
+    fn send(&mut self, from: NodeIndex, to: NodeIndex, msg: Message<W>) {
+        let queue = self.queues.entry((from, to)).or_default();
+        if queue.is_empty() {
+            self.slots.insert((from, to), self.pending.len());
+            self.pending.push(Link {
+                from,
+                to,
+                last_sent: self.sent,
+            });
+        } else {
+            self.pending[self.slots[&(from, to)]].last_sent = self.sent;
+        }
+        queue.push_back(msg);
+        self.sent += 1;
+    }
+}
+
+impl<W> Network<W> {
+    /// Removes the head of the link chosen by `scheduler`.
+    fn next(&mut self, scheduler: &mut dyn Scheduler) -> Option<(NodeIndex, Message<W>)> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let slot = scheduler.pick(&self.pending)?;
+        let link = self.pending[slot];
+        let queue = self
+            .queues
+            .get_mut(&(link.from, link.to))
+            .expect("Error while reading 'queues':");
+        let msg = queue.pop_front().expect("Error: pending link is empty:");
+        if queue.is_empty() {
+            self.slots.remove(&(link.from, link.to));
+            self.pending.swap_remove(slot);
+            if let Some(moved) = self.pending.get(slot) {
+                self.slots.insert((moved.from, moved.to), slot);
+            }
+        }
+        Some((link.to, msg))
+    }
+}
+
+/// Runs GHS on a single thread, delivering messages in an order drawn from `seed`.
+///
+/// The same graph and seed always produce the same run.
+pub fn run_simulated<N, W: Weight>(
+    graph: &Graph<N, W, Undirected>,
+    options: &Options,
+    seed: u64,
+    recorder: Option<&Arc<SnapshotRecorder<W>>>,
+) -> Outcome<W> {
+    run_scheduled(graph, options, &mut RandomScheduler::new(seed), recorder)
+}
+
+/// Same as `run_simulated`, delivering messages in the order `scheduler` picks.
+pub fn run_scheduled<N, W: Weight>(
+    graph: &Graph<N, W, Undirected>,
+    options: &Options,
+    scheduler: &mut dyn Scheduler,
+    recorder: Option<&Arc<SnapshotRecorder<W>>>,
+) -> Outcome<W> {
+    let node_indices: Vec<NodeIndex> = graph.node_indices().collect();
+    let mut nodes: HashMap<NodeIndex, Node<W>> = node_indices
+        .iter()
+        .map(|&node_index| (node_index, build_node(graph, node_index, options, recorder)))
+        .collect();
+
+    let mut network = Network {
+        queues: HashMap::new(),
+        pending: vec![],
+        slots: HashMap::new(),
+        sent: 0,
+    };
+    for node_index in &node_indices {
+        nodes
+            .get_mut(node_index)
+            .expect("Error while reading 'nodes':")
+            .initialize(&mut network);
+    }
+    while let Some((to, msg)) = network.next(scheduler) {
+        nodes
+            .get_mut(&to)
+            .expect("Error while reading 'nodes':")
+            .process(msg, &mut network);
+    }
+
+    collect(nodes.values())
+}
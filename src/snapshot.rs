@@ -0,0 +1,181 @@
+use crate::node::{EdgeKey, NodeSnapshot, Status};
+use crate::trace;
+use crate::weight::Weight;
+use petgraph::graph::NodeIndex;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+
+/// State of every node once a fragment has taken on its level and name.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct LevelSnapshot<W> {
+    /// Level of the fragment.
+    pub level: u32,
+    /// Name of the fragment.
+    pub name: EdgeKey<W>,
+    /// Latest published state of every node.
+    pub nodes: HashMap<NodeIndex, NodeSnapshot<W>>,
+}
+
+/// Collects `LevelSnapshot`s while a run is in progress.
+///
+/// Nodes publish their state whenever it changes. The members of a fragment
+/// are the nodes joined by edges that both ends mark `Branch`. Once every
+/// member of a merged or grown fragment carries the same `(level, name)`, i.e.
+/// the `Initiate` broadcast has reached all of them, the latest state of every
+/// node is copied into a snapshot. With the threaded backend the copy reflects
+/// what each node had published so far; nodes that have not woken up yet are
+/// missing from it.
+pub struct SnapshotRecorder<W> {
+    inner: Mutex<Recording<W>>,
+}
+
+impl<W: Weight> Default for SnapshotRecorder<W> {
+    fn default() -> Self {
+        SnapshotRecorder::new()
+    }
+}
+
+struct Recording<W> {
+    current: HashMap<NodeIndex, NodeSnapshot<W>>,
+    /// `(level, name, member count)` of every fragment captured so far; an
+    /// absorb keeps the level and name but adds members.
+    seen: BTreeSet<(u32, EdgeKey<W>, usize)>,
+    snapshots: Vec<LevelSnapshot<W>>,
+}
+
+impl<W: Weight> SnapshotRecorder<W> {
+    pub fn new() -> Self {
+        SnapshotRecorder {
+            inner: Mutex::new(Recording {
+                current: HashMap::new(),
+                seen: BTreeSet::new(),
+                snapshots: vec![],
+            }),
+        }
+    }
+
+    /// Stores the state of `node`, taking a snapshot if its fragment has just
+    /// finished a merge or an absorb.
+    pub(crate) fn publish(&self, node: NodeIndex, snapshot: NodeSnapshot<W>) {
+        let mut recording = self.inner.lock().expect("Error while locking 'inner':");
+        /* Only a new level, name or branch can complete a fragment */
+        let changed = recording.current.get(&node).is_none_or(|old| {
+            (old.level, old.name, branch_count(old))
+                != (snapshot.level, snapshot.name, branch_count(&snapshot))
+        });
+        let fragment = snapshot.name.map(|name| (snapshot.level, name));
+        recording.current.insert(node, snapshot);
+        if !changed {
+            return;
+        }
+        if let Some((level, name)) = fragment {
+            if let Some(members) = recording.members(node, level, name) {
+                if recording.seen.insert((level, name, members)) {
+                    recording.take(level, name);
+                }
+            }
+        }
+    }
+
+    /// Replaces the published states with the final state of every node and
+    /// takes a last snapshot, labelled with the fragment at the highest level.
+    pub(crate) fn record_end(&self, nodes: &HashMap<NodeIndex, NodeSnapshot<W>>) {
+        let mut recording = self.inner.lock().expect("Error while locking 'inner':");
+        recording.current = nodes.clone();
+        let last = nodes
+            .values()
+            .filter_map(|node| node.name.map(|name| (node.level, name)))
+            .max();
+        if let Some((level, name)) = last {
+            recording.take(level, name);
+        }
+    }
+
+    /// Removes and returns the snapshots taken so far.
+    pub fn take_snapshots(&self) -> Vec<LevelSnapshot<W>> {
+        let mut recording = self.inner.lock().expect("Error while locking 'inner':");
+        std::mem::take(&mut recording.snapshots)
+    }
+}
+
+impl<W: Weight> Recording<W> {
+    /// Number of members of the fragment of `start`, or `None` if some of them
+    /// do not carry `(level, name)` yet.
+    fn members(&self, start: NodeIndex, level: u32, name: EdgeKey<W>) -> Option<usize> {
+        let mut visited = BTreeSet::from([start]);
+        let mut stack = vec![start];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.current[&node_index];
+            if node.level != level || node.name != Some(name) {
+                return None;
+            }
+            for (&nbr, &status) in &node.status {
+                let mutual = self
+                    .current
+                    .get(&nbr)
+                    .and_then(|nbr| nbr.status.get(&node_index))
+                    == Some(&Status::Branch);
+                if status == Status::Branch && mutual && visited.insert(nbr) {
+                    stack.push(nbr);
+                }
+            }
+        }
+        Some(visited.len())
+    }
+
+    /// Appends a copy of the latest state of every node.
+    fn take(&mut self, level: u32, name: EdgeKey<W>) {
+        let nodes = self.current.clone();
+        self.snapshots.push(LevelSnapshot { level, name, nodes });
+    }
+}
+
+fn branch_count<W>(node: &NodeSnapshot<W>) -> usize {
+    node.status
+        .values()
+        .filter(|&&status| status == Status::Branch)
+        .count()
+}
+
+/// Renders `snapshots` as a JSON array.
+///
+/// Each element holds the level and fragment name that triggered it and, per
+/// node, its level, fragment name and the neighbours of its `Branch` edges.
+pub fn to_json<W: Weight>(snapshots: &[LevelSnapshot<W>]) -> String {
+    let snapshots: Vec<String> = snapshots
+        .iter()
+        .map(|snapshot| {
+            let mut node_indices: Vec<&NodeIndex> = snapshot.nodes.keys().collect();
+            node_indices.sort();
+            let nodes: Vec<String> = node_indices
+                .into_iter()
+                .map(|node_index| {
+                    let node = &snapshot.nodes[node_index];
+                    let mut branches: Vec<usize> = node
+                        .status
+                        .iter()
+                        .filter(|(_, status)| **status == Status::Branch)
+                        .map(|(nbr, _)| nbr.index())
+                        .collect();
+                    branches.sort_unstable();
+                    let branches: Vec<String> =
+                        branches.iter().map(|nbr| nbr.to_string()).collect();
+                    format!(
+                        "{{\"node\":{},\"level\":{},\"name\":{},\"branches\":[{}]}}",
+                        node_index.index(),
+                        node.level,
+                        trace::name_json(&node.name),
+                        branches.join(",")
+                    )
+                })
+                .collect();
+            format!(
+                "{{\"level\":{},\"name\":{},\"nodes\":[{}]}}",
+                snapshot.level,
+                trace::edge_key_json(&snapshot.name),
+                nodes.join(",")
+            )
+        })
+        .collect();
+    format!("[{}]\n", snapshots.join(",\n"))
+}
@@ -0,0 +1,106 @@
+use crate::node::Message;
+use std::fmt;
+use std::time::Duration;
+
+/// Message counts and other figures collected during a GHS run.
+///
+/// Each node counts the messages it sends; the runner merges the per-node
+/// counts and measures the wall-clock time.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct Stats {
+    pub connect: u64,
+    pub initiate: u64,
+    pub test: u64,
+    pub accept: u64,
+    pub reject: u64,
+    pub report: u64,
+    pub change_root: u64,
+    /// Termination broadcast, not part of the original protocol.
+    pub halt: u64,
+    /// Times a node could not handle a message yet and deferred it; a message
+    /// retried and deferred again counts again.
+    pub requeued: u64,
+    /// Highest fragment level any node reached.
+    pub max_level: u32,
+    pub elapsed: Duration,
+}
+
+impl Stats {
+    /// Counts one sent message.
+    pub fn count<W>(&mut self, msg: &Message<W>) {
+        match msg {
+            Message::Connect(..) => self.connect += 1,
+            Message::Initiate(..) => self.initiate += 1,
+            Message::Test(..) => self.test += 1,
+            Message::Accept(..) => self.accept += 1,
+            Message::Reject(..) => self.reject += 1,
+            Message::Report(..) => self.report += 1,
+            Message::ChangeRoot(..) => self.change_root += 1,
+            Message::Halt(..) => self.halt += 1,
+        }
+    }
+
+    /// Number of GHS protocol messages, i.e. everything except `Halt`.
+    pub fn messages(&self) -> u64 {
+        self.connect
+            + self.initiate
+            + self.test
+            + self.accept
+            + self.reject
+            + self.report
+            + self.change_root
+    }
+
+    /// Adds the counts of `other` to `self`.
+    pub fn merge(&mut self, other: &Stats) {
+        self.connect += other.connect;
+        self.initiate += other.initiate;
+        self.test += other.test;
+        self.accept += other.accept;
+        self.reject += other.reject;
+        self.report += other.report;
+        self.change_root += other.change_root;
+        self.halt += other.halt;
+        self.requeued += other.requeued;
+        self.max_level = self.max_level.max(other.max_level);
+        self.elapsed = self.elapsed.max(other.elapsed);
+    }
+
+    /// Renders the statistics as a single-line JSON object.
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"messages\":{},\"connect\":{},\"initiate\":{},\"test\":{},\"accept\":{},\
+             \"reject\":{},\"report\":{},\"change_root\":{},\"halt\":{},\"requeued\":{},\
+             \"max_level\":{},\"elapsed_ms\":{}}}",
+            self.messages(),
+            self.connect,
+            self.initiate,
+            self.test,
+            self.accept,
+            self.reject,
+            self.report,
+            self.change_root,
+            self.halt,
+            self.requeued,
+            self.max_level,
+            self.elapsed.as_secs_f64() * 1000.0
+        )
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "messages: {}", self.messages())?;
+        writeln!(f, "  connect: {}", self.connect)?;
+        writeln!(f, "  initiate: {}", self.initiate)?;
+        writeln!(f, "  test: {}", self.test)?;
+        writeln!(f, "  accept: {}", self.accept)?;
+        writeln!(f, "  reject: {}", self.reject)?;
+        writeln!(f, "  report: {}", self.report)?;
+        writeln!(f, "  change_root: {}", self.change_root)?;
+        writeln!(f, "halt: {}", self.halt)?;
+        writeln!(f, "requeued: {}", self.requeued)?;
+        writeln!(f, "max level: {}", self.max_level)?;
+        write!(f, "elapsed: {:.3} ms", self.elapsed.as_secs_f64() * 1000.0)
+    }
+}
@@ -0,0 +1,259 @@
+use crate::json::Value;
+use crate::node::{BestWeight, EdgeKey, Message, State, Status};
+use crate::weight::Weight;
+use crate::{Backend, Options};
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use petgraph::Undirected;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io;
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Records a GHS run as JSON Lines, one event per line.
+///
+/// The first line describes the graph and the options of the run. Every
+/// following line is one event of a node: `init`, `send`, `receive` (a message taken
+/// from the network), `retry` (a deferred message handled again), `defer`,
+/// `status`, `state` or `halt`. Each event carries the node's level, fragment
+/// name and state at the time it was recorded, a `seq` number that totally
+/// orders the trace and the microseconds elapsed since the tracer was created.
+///
+/// Nodes on different threads share one `Tracer`; a `send` is always recorded
+/// before the matching `receive`, so `seq` is consistent with causality. A
+/// final `end` event marks a run that finished; each line is handed to the
+/// writer in one call, so an unbuffered trace of a run that had to be killed
+/// is complete up to its last event.
+pub struct Tracer {
+    start: Instant,
+    out: Mutex<TraceOut>,
+}
+
+struct TraceOut {
+    seq: u64,
+    writer: Box<dyn Write + Send>,
+    /// First write error; recording stops once one occurred.
+    error: Option<io::Error>,
+}
+
+impl Tracer {
+    /// Creates a tracer writing to `writer`.
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Tracer {
+            start: Instant::now(),
+            out: Mutex::new(TraceOut {
+                seq: 0,
+                writer: Box::new(writer),
+                error: None,
+            }),
+        }
+    }
+
+    /// Writes one event; `fields` is a comma-prefixed list of JSON members.
+    pub(crate) fn record(&self, event: &str, fields: &str) {
+        let mut out = self.out.lock().expect("Error while locking 'out':");
+        if out.error.is_some() {
+            return;
+        }
+        let line = format!(
+            "{{\"seq\":{},\"time_us\":{},\"event\":\"{}\"{}}}\n",
+            out.seq,
+            self.start.elapsed().as_micros(),
+            event,
+            fields
+        );
+        out.seq += 1;
+        if let Err(err) = out.writer.write_all(line.as_bytes()) {
+            out.error = Some(err);
+        }
+    }
+
+    /// Writes the header line: the graph and the options it is run with.
+    pub(crate) fn record_graph<N, W: Weight>(
+        &self,
+        graph: &Graph<N, W, Undirected>,
+        options: &Options,
+    ) {
+        let edges: Vec<String> = graph
+            .edge_references()
+            .map(|edge| {
+                format!(
+                    "[{},{},\"{}\"]",
+                    edge.source().index(),
+                    edge.target().index(),
+                    edge.weight()
+                )
+            })
+            .collect();
+        let backend = match options.backend {
+            Backend::Threads => String::from("\"threads\""),
+            Backend::Pool { threads } => format!("\"pool\",\"threads\":{}", threads),
+            Backend::Simulated { seed } => format!("\"sim\",\"seed\":{}", seed),
+        };
+        self.record(
+            "graph",
+            &format!(
+                ",\"weights\":\"{}\",\"tie_break\":{},\"backend\":{},\"nodes\":{},\"edges\":[{}]",
+                W::NAME,
+                options.tie_break,
+                backend,
+                graph.node_count(),
+                edges.join(",")
+            ),
+        );
+    }
+
+    /// Writes the `end` event once every node has halted.
+    pub(crate) fn record_end(&self) {
+        self.record("end", "");
+    }
+
+    /// Flushes the output and reports the first error hit while writing.
+    pub fn finish(&self) -> io::Result<()> {
+        let mut out = self.out.lock().expect("Error while locking 'out':");
+        match out.error.take() {
+            Some(err) => Err(err),
+            None => out.writer.flush(),
+        }
+    }
+}
+
+impl fmt::Debug for Tracer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Tracer").finish_non_exhaustive()
+    }
+}
+
+/// JSON form of an edge key; the weight is a string so that every `Weight` round-trips.
+pub(crate) fn edge_key_json<W: Weight>(key: &EdgeKey<W>) -> String {
+    format!(
+        "{{\"weight\":\"{}\",\"low\":{},\"high\":{}}}",
+        key.weight, key.low, key.high
+    )
+}
+
+/// JSON form of a fragment name, `null` before the first `Initiate`.
+pub(crate) fn name_json<W: Weight>(name: &Option<EdgeKey<W>>) -> String {
+    match name {
+        Some(key) => edge_key_json(key),
+        None => String::from("null"),
+    }
+}
+
+pub(crate) fn best_weight_json<W: Weight>(best: &BestWeight<W>) -> String {
+    match best {
+        BestWeight::Edge(key) => edge_key_json(key),
+        BestWeight::Infinity => String::from("\"inf\""),
+    }
+}
+
+pub(crate) fn state_json(state: State) -> &'static str {
+    match state {
+        State::Sleep => "\"Sleep\"",
+        State::Find => "\"Find\"",
+        State::Found => "\"Found\"",
+    }
+}
+
+pub(crate) fn status_json(status: Status) -> &'static str {
+    match status {
+        Status::Basic => "\"Basic\"",
+        Status::Branch => "\"Branch\"",
+        Status::Reject => "\"Reject\"",
+    }
+}
+
+pub(crate) fn message_json<W: Weight>(msg: &Message<W>) -> String {
+    match msg {
+        Message::Connect(level, from) => format!(
+            "{{\"type\":\"Connect\",\"level\":{},\"from\":{}}}",
+            level,
+            from.index()
+        ),
+        Message::Initiate(level, name, state, from) => format!(
+            "{{\"type\":\"Initiate\",\"level\":{},\"name\":{},\"state\":{},\"from\":{}}}",
+            level,
+            edge_key_json(name),
+            state_json(*state),
+            from.index()
+        ),
+        Message::Test(level, name, from) => format!(
+            "{{\"type\":\"Test\",\"level\":{},\"name\":{},\"from\":{}}}",
+            level,
+            edge_key_json(name),
+            from.index()
+        ),
+        Message::Accept(from) => format!("{{\"type\":\"Accept\",\"from\":{}}}", from.index()),
+        Message::Reject(from) => format!("{{\"type\":\"Reject\",\"from\":{}}}", from.index()),
+        Message::Report(best, from) => format!(
+            "{{\"type\":\"Report\",\"best\":{},\"from\":{}}}",
+            best_weight_json(best),
+            from.index()
+        ),
+        Message::ChangeRoot(from) => {
+            format!("{{\"type\":\"ChangeRoot\",\"from\":{}}}", from.index())
+        }
+        Message::Halt(from) => format!("{{\"type\":\"Halt\",\"from\":{}}}", from.index()),
+    }
+}
+
+/* Inverse of the functions above, used to read traces back */
+
+pub(crate) fn edge_key_from_json<W: FromStr>(value: &Value) -> Option<EdgeKey<W>> {
+    Some(EdgeKey {
+        weight: W::from_str(value.get("weight")?.as_str()?).ok()?,
+        low: u32::try_from(value.get("low")?.as_u64()?).ok()?,
+        high: u32::try_from(value.get("high")?.as_u64()?).ok()?,
+    })
+}
+
+pub(crate) fn name_from_json<W: FromStr>(value: &Value) -> Option<Option<EdgeKey<W>>> {
+    match value {
+        Value::Null => Some(None),
+        _ => edge_key_from_json(value).map(Some),
+    }
+}
+
+pub(crate) fn state_from_json(value: &Value) -> Option<State> {
+    match value.as_str()? {
+        "Sleep" => Some(State::Sleep),
+        "Find" => Some(State::Find),
+        "Found" => Some(State::Found),
+        _ => None,
+    }
+}
+
+pub(crate) fn node_from_json(value: &Value) -> Option<NodeIndex> {
+    Some(NodeIndex::new(usize::try_from(value.as_u64()?).ok()?))
+}
+
+pub(crate) fn message_from_json<W: FromStr>(value: &Value) -> Option<Message<W>> {
+    let from = node_from_json(value.get("from")?)?;
+    let level = || u32::try_from(value.get("level")?.as_u64()?).ok();
+    let name = || edge_key_from_json(value.get("name")?);
+    match value.get("type")?.as_str()? {
+        "Connect" => Some(Message::Connect(level()?, from)),
+        "Initiate" => Some(Message::Initiate(
+            level()?,
+            name()?,
+            state_from_json(value.get("state")?)?,
+            from,
+        )),
+        "Test" => Some(Message::Test(level()?, name()?, from)),
+        "Accept" => Some(Message::Accept(from)),
+        "Reject" => Some(Message::Reject(from)),
+        "Report" => {
+            let best = match value.get("best")? {
+                Value::String(text) if text == "inf" => BestWeight::Infinity,
+                best => BestWeight::Edge(edge_key_from_json(best)?),
+            };
+            Some(Message::Report(best, from))
+        }
+        "ChangeRoot" => Some(Message::ChangeRoot(from)),
+        "Halt" => Some(Message::Halt(from)),
+        _ => None,
+    }
+}
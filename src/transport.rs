@@ -0,0 +1,118 @@
+use crate::node::Message;
+use petgraph::graph::NodeIndex;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Condvar, Mutex};
+
+/// How a `Node` hands its outgoing messages to the network.
+///
+/// Every backend implements this, so the `process_*` handlers run unchanged
+/// whether nodes live on their own threads or inside the simulator. Backends
+/// model one FIFO channel per directed edge: messages on different links may be
+/// delivered in any order, messages on the same link never overtake each other.
+pub trait Transport<W> {
+    /// Queues `msg` from node `from` for delivery to node `to`.
+    fn send(&mut self, from: NodeIndex, to: NodeIndex, msg: Message<W>);
+}
+
+/// Receiving end of a node in the threaded backend: one FIFO queue per incoming link.
+pub struct Inbox<W> {
+    links: Mutex<Links<W>>,
+    ready: Condvar,
+}
+
+struct Links<W> {
+    queues: HashMap<NodeIndex, VecDeque<Message<W>>>,
+    /// Neighbours in the order links are serviced.
+    order: Vec<NodeIndex>,
+    /// Position in `order` of the link to look at first on the next `recv`.
+    next: usize,
+}
+
+impl<W> Inbox<W> {
+    /// Creates an inbox with one empty link per neighbour.
+    pub fn new(neighbours: Vec<NodeIndex>) -> Self {
+        Inbox {
+            links: Mutex::new(Links {
+                queues: neighbours
+                    .iter()
+                    .map(|&nbr| (nbr, VecDeque::new()))
+                    .collect(),
+                order: neighbours,
+                next: 0,
+            }),
+            ready: Condvar::new(),
+        }
+    }
+
+    pub(crate) fn push(&self, from: NodeIndex, msg: Message<W>) {
+        let mut links = self.links.lock().expect("Error while locking 'links':");
+        links
+            .queues
+            .get_mut(&from)
+            .expect("Error: message on a link that does not exist:")
+            .push_back(msg);
+        self.ready.notify_one();
+    }
+
+    /// Blocks until a message is available, servicing non-empty links round-robin.
+    pub fn recv(&self) -> Message<W> {
+        let mut links = self.links.lock().expect("Error while locking 'links':");
+        loop {
+            if let Some(msg) = links.pop() {
+                return msg;
+            }
+            links = self
+                .ready
+                .wait(links)
+                .expect("Error while waiting on 'ready':");
+        }
+    }
+
+    /// Same as `recv`, returning `None` instead of blocking when every link is empty.
+    pub fn try_recv(&self) -> Option<Message<W>> {
+        self.links
+            .lock()
+            .expect("Error while locking 'links':")
+            .pop()
+    }
+
+    /// Whether any link holds a message.
+    pub fn has_messages(&self) -> bool {
+        self.links
+            .lock()
+            .expect("Error while locking 'links':")
+            .queues
+            .values()
+            .any(|queue| !queue.is_empty())
+    }
+}
+
+impl<W> Links<W> {
+    /// Takes the next message, starting from the link after the last one serviced.
+    fn pop(&mut self) -> Option<Message<W>> {
+        let count = self.order.len();
+        for offset in 0..count {
+            let slot = (self.next + offset) % count;
+            let nbr = self.order[slot];
+            let msg = self
+                .queues
+                .get_mut(&nbr)
+                .expect("Error while reading 'queues':")
+                .pop_front();
+            if msg.is_some() {
+                self.next = (slot + 1) % count;
+                return msg;
+            }
+        }
+        None
+    }
+}
+
+/// Sending side of the threaded backend, shared by every node thread.
+impl<W> Transport<W> for Arc<HashMap<NodeIndex, Inbox<W>>> {
+    fn send(&mut self, from: NodeIndex, to: NodeIndex, msg: Message<W>) {
+        self.get(&to)
+            .expect("Error while reading 'inboxes':")
+            .push(from, msg);
+    }
+}
@@ -0,0 +1,172 @@
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use petgraph::Undirected;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+
+use crate::weight::Weight;
+
+/// A violation of the input assumptions GHS relies on.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Problem<W> {
+    /// Several edges share `weight`, so fragment names would collide.
+    DuplicateWeight {
+        weight: W,
+        edges: Vec<(NodeIndex, NodeIndex)>,
+    },
+    /// An edge from a node to itself.
+    SelfLoop { node: NodeIndex, weight: W },
+    /// More than one edge between the same pair of nodes.
+    ParallelEdges {
+        source: NodeIndex,
+        target: NodeIndex,
+        weights: Vec<W>,
+    },
+    /// A node without any edge. `run_ghs` treats it as a single-node tree.
+    IsolatedNode(NodeIndex),
+    /// The graph splits into several connected components. `run_ghs` returns a
+    /// spanning forest in that case.
+    Disconnected { components: Vec<Vec<NodeIndex>> },
+}
+
+impl<W: Weight> fmt::Display for Problem<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::DuplicateWeight { weight, edges } => {
+                write!(f, "weight {} is shared by edges", weight)?;
+                for (source, target) in edges {
+                    write!(f, " ({}, {})", source.index(), target.index())?;
+                }
+                Ok(())
+            }
+            Problem::SelfLoop { node, weight } => write!(
+                f,
+                "self-loop ({}, {}, {})",
+                node.index(),
+                node.index(),
+                weight
+            ),
+            Problem::ParallelEdges {
+                source,
+                target,
+                weights,
+            } => write!(
+                f,
+                "parallel edges between {} and {} with weights {:?}",
+                source.index(),
+                target.index(),
+                weights
+            ),
+            Problem::IsolatedNode(node) => write!(f, "node {} has no edges", node.index()),
+            Problem::Disconnected { components } => {
+                write!(f, "graph has {} connected components:", components.len())?;
+                for component in components {
+                    let nodes: Vec<usize> = component.iter().map(|node| node.index()).collect();
+                    write!(f, " {:?}", nodes)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Every precondition violation found in a graph.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ValidationError<W> {
+    pub problems: Vec<Problem<W>>,
+}
+
+impl<W: Weight> fmt::Display for ValidationError<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "graph does not satisfy the GHS preconditions:")?;
+        for problem in &self.problems {
+            write!(f, "\n  {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Weight> Error for ValidationError<W> {}
+
+/// Checks that `graph` is connected, loop-free, simple and has unique edge weights.
+pub fn validate_graph<N, W: Weight>(
+    graph: &Graph<N, W, Undirected>,
+) -> Result<(), ValidationError<W>> {
+    let mut problems = vec![];
+
+    let mut by_weight: BTreeMap<W, Vec<(NodeIndex, NodeIndex)>> = BTreeMap::new();
+    let mut by_pair: BTreeMap<(NodeIndex, NodeIndex), Vec<W>> = BTreeMap::new();
+    for edge in graph.edge_references() {
+        let (source, target, weight) = (edge.source(), edge.target(), *edge.weight());
+        if source == target {
+            problems.push(Problem::SelfLoop {
+                node: source,
+                weight,
+            });
+            continue;
+        }
+        let pair = (source.min(target), source.max(target));
+        by_weight.entry(weight).or_default().push(pair);
+        by_pair.entry(pair).or_default().push(weight);
+    }
+    for ((source, target), weights) in by_pair {
+        if weights.len() > 1 {
+            problems.push(Problem::ParallelEdges {
+                source,
+                target,
+                weights,
+            });
+        }
+    }
+    for (weight, edges) in by_weight {
+        if edges.len() > 1 {
+            problems.push(Problem::DuplicateWeight { weight, edges });
+        }
+    }
+
+    for node in graph.node_indices() {
+        if graph.neighbors(node).all(|nbr| nbr == node) {
+            problems.push(Problem::IsolatedNode(node));
+        }
+    }
+    let components = components(graph);
+    if components.len() > 1 {
+        problems.push(Problem::Disconnected { components });
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(ValidationError { problems })
+    }
+}
+
+/// Connected components of `graph`, each sorted by node index.
+pub fn components<N, W>(graph: &Graph<N, W, Undirected>) -> Vec<Vec<NodeIndex>> {
+    let mut component_of: HashMap<NodeIndex, usize> = HashMap::new();
+    let mut components = vec![];
+    for start in graph.node_indices() {
+        if component_of.contains_key(&start) {
+            continue;
+        }
+        let id = components.len();
+        let mut component = vec![start];
+        component_of.insert(start, id);
+        let mut next = 0;
+        while next < component.len() {
+            let node = component[next];
+            next += 1;
+            for nbr in graph.neighbors(node) {
+                if let Entry::Vacant(entry) = component_of.entry(nbr) {
+                    entry.insert(id);
+                    component.push(nbr);
+                }
+            }
+        }
+        component.sort_unstable();
+        components.push(component);
+    }
+    components
+}
@@ -0,0 +1,101 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::fmt::{Debug, Display};
+use std::iter::Sum;
+use std::str::FromStr;
+
+/// An edge weight GHS can run on.
+///
+/// Weights only need a total order: a fragment without an outgoing edge reports
+/// `BestWeight::Infinity` rather than a reserved weight value, so the whole range
+/// of the type is usable.
+pub trait Weight: Copy + Ord + Debug + Display + Send + Sync + 'static {
+    /// Type the weights of a whole tree are summed in.
+    type Total: Copy + PartialEq + Debug + Display + Sum;
+
+    /// Name of the type on the command line and in traces.
+    const NAME: &'static str;
+
+    fn to_total(self) -> Self::Total;
+}
+
+macro_rules! integer_weight {
+    ($($weight:ty => $total:ty),*) => {
+        $(
+            impl Weight for $weight {
+                type Total = $total;
+
+                const NAME: &'static str = stringify!($weight);
+
+                fn to_total(self) -> $total {
+                    self as $total
+                }
+            }
+        )*
+    };
+}
+
+integer_weight!(i32 => i64, i64 => i128, u32 => u64, u64 => u128);
+
+/// `f64` ordered by `f64::total_cmp`, for floating point weights.
+///
+/// Parsing rejects NaN.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TotalF64(pub f64);
+
+impl PartialEq for TotalF64 {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for TotalF64 {}
+
+impl PartialOrd for TotalF64 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TotalF64 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl fmt::Display for TotalF64 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+/// Error returned when a `TotalF64` cannot be parsed.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ParseTotalF64Error;
+
+impl fmt::Display for ParseTotalF64Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid float literal")
+    }
+}
+
+impl FromStr for TotalF64 {
+    type Err = ParseTotalF64Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match f64::from_str(s) {
+            Ok(value) if !value.is_nan() => Ok(TotalF64(value)),
+            _ => Err(ParseTotalF64Error),
+        }
+    }
+}
+
+impl Weight for TotalF64 {
+    type Total = f64;
+
+    const NAME: &'static str = "f64";
+
+    fn to_total(self) -> f64 {
+        self.0
+    }
+}
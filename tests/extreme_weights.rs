@@ -0,0 +1,66 @@
+use ghs::{kruskal, run_ghs, run_ghs_from_edges, verify, TotalF64, Weight};
+use petgraph::graph::Graph;
+use petgraph::Undirected;
+
+fn assert_matches_kruskal<W: Weight>(edges: &[(u32, u32, W)]) {
+    let mut graph: Graph<i32, W, Undirected> = Graph::default();
+    graph.extend_with_edges(edges);
+    let mst = run_ghs(&graph).expect("graph should be valid");
+    let verification = verify(&mst, &kruskal(&graph));
+    assert!(verification.edges_equal(), "{}", verification);
+    assert_eq!(mst.edges.len(), graph.node_count() - 1);
+}
+
+#[test]
+fn i32_max_edge_is_not_mistaken_for_no_outgoing_edge() {
+    let mst = run_ghs_from_edges(&[(0, 1, 1), (1, 2, i32::MAX)]).unwrap();
+    let weights: Vec<i32> = mst.edges.iter().map(|edge| edge.weight).collect();
+    assert_eq!(weights, vec![1, i32::MAX]);
+}
+
+#[test]
+fn i32_extremes() {
+    assert_matches_kruskal(&[
+        (0, 1, i32::MAX),
+        (1, 2, i32::MIN),
+        (2, 3, i32::MAX - 1),
+        (3, 0, 0),
+        (0, 2, i32::MIN + 1),
+        (1, 3, -1),
+    ]);
+}
+
+#[test]
+fn i32_max_bridges_between_fragments() {
+    /* Two triangles joined only by the heaviest possible edge */
+    assert_matches_kruskal(&[
+        (0, 1, 1),
+        (1, 2, 2),
+        (2, 0, 3),
+        (3, 4, 4),
+        (4, 5, 5),
+        (5, 3, 6),
+        (2, 3, i32::MAX),
+    ]);
+}
+
+#[test]
+fn u64_max_edge() {
+    assert_matches_kruskal(&[
+        (0, 1, u64::MAX),
+        (1, 2, 0u64),
+        (2, 3, u64::MAX - 1),
+        (0, 3, 7),
+    ]);
+}
+
+#[test]
+fn float_infinities() {
+    assert_matches_kruskal(&[
+        (0, 1, TotalF64(f64::INFINITY)),
+        (1, 2, TotalF64(f64::NEG_INFINITY)),
+        (2, 3, TotalF64(f64::MAX)),
+        (3, 4, TotalF64(-0.5)),
+        (4, 0, TotalF64(1e300)),
+    ]);
+}
@@ -0,0 +1,73 @@
+use ghs::generate::{generate, random_connected, Family, GenerateError};
+use ghs::{kruskal, run_ghs, validate_graph, verify};
+
+const FAMILIES: [Family; 9] = [
+    Family::ErdosRenyi,
+    Family::Geometric,
+    Family::Grid,
+    Family::Torus,
+    Family::Complete,
+    Family::Ring,
+    Family::Star,
+    Family::TreeChords,
+    Family::BarabasiAlbert,
+];
+
+#[test]
+fn every_family_is_connected_with_unique_weights() {
+    for family in FAMILIES {
+        for nodes in [2, 3, 4, 9, 30] {
+            for degree in [0.0, 1.0, 4.0, 12.0, 1e30] {
+                let graph = generate(family, nodes, degree, 7).to_graph();
+                if let Err(err) = validate_graph(&graph) {
+                    panic!(
+                        "{} with {} nodes, degree {}: {}",
+                        family, nodes, degree, err
+                    );
+                }
+                let mst = run_ghs(&graph).unwrap();
+                assert!(verify(&mst, &kruskal(&graph)).edges_equal());
+            }
+        }
+    }
+}
+
+#[test]
+fn same_seed_same_graph() {
+    for family in FAMILIES {
+        assert_eq!(generate(family, 40, 5.0, 3), generate(family, 40, 5.0, 3));
+        assert_eq!(family.to_string().parse(), Ok(family));
+    }
+}
+
+#[test]
+fn fixed_shapes_have_their_edge_counts() {
+    let edges = |family| generate(family, 12, 4.0, 0).edges.len();
+    assert_eq!(edges(Family::Complete), 66);
+    assert_eq!(edges(Family::Ring), 12);
+    assert_eq!(edges(Family::Star), 11);
+    /* 3 rows of 4 */
+    assert_eq!(edges(Family::Grid), 17);
+    assert_eq!(edges(Family::Torus), 24);
+}
+
+#[test]
+fn random_connected_has_exactly_the_requested_edges() {
+    assert_eq!(random_connected(10, 45, 1).unwrap().edges.len(), 45);
+    assert_eq!(random_connected(10, 9, 1).unwrap().edges.len(), 9);
+    assert_eq!(
+        random_connected(10, 8, 1),
+        Err(GenerateError::TooFewEdges {
+            nodes: 10,
+            edges: 8
+        })
+    );
+    assert_eq!(
+        random_connected(10, 46, 1),
+        Err(GenerateError::TooManyEdges {
+            nodes: 10,
+            edges: 46,
+            max: 45
+        })
+    );
+}
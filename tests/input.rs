@@ -0,0 +1,277 @@
+use ghs::input::{parse_dimacs, parse_edge_list, parse_matrix_market, MAX_NODES};
+use ghs::{parse_graph, Format, ParseError, ParseErrorKind};
+use petgraph::graph::Graph;
+use petgraph::visit::EdgeRef;
+use petgraph::Undirected;
+
+/// Edges of `graph` as sorted `(min, max, weight)` triples.
+fn edges(graph: &Graph<i32, i32, Undirected>) -> Vec<(usize, usize, i32)> {
+    let mut edges: Vec<(usize, usize, i32)> = graph
+        .edge_references()
+        .map(|edge| {
+            let (u, v) = (edge.source().index(), edge.target().index());
+            (u.min(v), u.max(v), *edge.weight())
+        })
+        .collect();
+    edges.sort_unstable();
+    edges
+}
+
+fn error(line: usize, column: usize, text: &str, kind: ParseErrorKind) -> ParseError {
+    ParseError {
+        line,
+        column,
+        text: text.to_string(),
+        kind,
+    }
+}
+
+#[test]
+fn dimacs_merges_opposite_arcs_into_0_based_edges() {
+    let input = "c a triangle\np sp 3 5\na 1 2 5\na 2 1 5\nc\na 2 3 7\na 3 2 7\na 1 3 9\n";
+    let graph = parse_dimacs::<i32>(input).unwrap();
+    assert_eq!(graph.node_count(), 3);
+    assert_eq!(edges(&graph), vec![(0, 1, 5), (0, 2, 9), (1, 2, 7)]);
+
+    /* Opposite arcs of different weights stay two edges */
+    let graph = parse_dimacs::<i32>("p sp 2 2\na 1 2 5\na 2 1 6\n").unwrap();
+    assert_eq!(edges(&graph), vec![(0, 1, 5), (0, 1, 6)]);
+}
+
+#[test]
+fn dimacs_errors() {
+    assert_eq!(
+        parse_dimacs::<i32>("p sp 2 3\na 1 2 5\na 2 1 5\n").unwrap_err(),
+        error(
+            3,
+            1,
+            "",
+            ParseErrorKind::EdgeCountMismatch {
+                expected: 3,
+                found: 2
+            }
+        )
+    );
+    assert_eq!(
+        parse_dimacs::<i32>("p sp 2 1\na 0 2 5\n").unwrap_err(),
+        error(2, 3, "0", ParseErrorKind::IndexOutOfRange { nodes: 2 })
+    );
+    assert_eq!(
+        parse_dimacs::<i32>("c only comments\n").unwrap_err().kind,
+        ParseErrorKind::MissingHeader
+    );
+    assert_eq!(
+        parse_dimacs::<i32>("p sp 2 1\ne 1 2\n").unwrap_err(),
+        error(2, 1, "e 1 2", ParseErrorKind::MalformedEdge)
+    );
+    assert_eq!(
+        parse_dimacs::<i32>("c huge\np sp 4294967295 0\n").unwrap_err(),
+        error(2, 6, "4294967295", ParseErrorKind::InvalidHeader)
+    );
+    let nodes = (MAX_NODES + 1).to_string();
+    assert_eq!(
+        parse_dimacs::<i32>(&format!("p sp {} 0\n", nodes)).unwrap_err(),
+        error(1, 6, &nodes, ParseErrorKind::InvalidHeader)
+    );
+}
+
+#[test]
+fn edge_list_reads_separators_headers_and_comments() {
+    let input = "source,target,weight\n# a comment\n0,1,5\n\n% another\n1, 3, 2\n";
+    let graph = parse_edge_list::<i32>(input).unwrap();
+    assert_eq!(graph.node_count(), 4);
+    assert_eq!(edges(&graph), vec![(0, 1, 5), (1, 3, 2)]);
+
+    let graph = parse_edge_list::<i32>("0 1 5\n2\t1\t3\n").unwrap();
+    assert_eq!(graph.node_count(), 3);
+    assert_eq!(edges(&graph), vec![(0, 1, 5), (1, 2, 3)]);
+}
+
+#[test]
+fn edge_list_rejects_indices_past_the_node_limit() {
+    let out_of_range = ParseErrorKind::IndexOutOfRange { nodes: MAX_NODES };
+    for index in ["4294967295", "4000000000", "99999999999"] {
+        assert_eq!(
+            parse_edge_list::<i32>(&format!("0 {} 1\n", index)).unwrap_err(),
+            error(1, 3, index, out_of_range.clone())
+        );
+    }
+    let last = MAX_NODES - 1;
+    assert_eq!(
+        parse_edge_list::<i32>(&format!("0 1 1\n{} 1 1\n", last + 1)).unwrap_err(),
+        error(2, 1, &(last + 1).to_string(), out_of_range)
+    );
+    assert_eq!(
+        parse_edge_list::<i32>("0 -1 1\n").unwrap_err(),
+        error(1, 3, "-1", ParseErrorKind::NegativeIndex)
+    );
+    assert_eq!(
+        parse_edge_list::<i32>("0 1\n").unwrap_err(),
+        error(1, 1, "0 1", ParseErrorKind::MalformedEdge)
+    );
+}
+
+#[test]
+fn matrix_market_merges_general_entries_and_reads_symmetric_ones() {
+    let general =
+        "%%MatrixMarket matrix coordinate integer general\n% comment\n3 3 3\n1 2 4\n2 1 4\n3 1 6\n";
+    let graph = parse_matrix_market::<i32>(general).unwrap();
+    assert_eq!(graph.node_count(), 3);
+    assert_eq!(edges(&graph), vec![(0, 1, 4), (0, 2, 6)]);
+
+    let symmetric = "%%MatrixMarket matrix coordinate real symmetric\n3 3 2\n2 1 4\n3 2 4\n";
+    let graph = parse_matrix_market::<i32>(symmetric).unwrap();
+    assert_eq!(edges(&graph), vec![(0, 1, 4), (1, 2, 4)]);
+}
+
+#[test]
+fn matrix_market_errors() {
+    assert_eq!(
+        parse_matrix_market::<i32>("%%MatrixMarket matrix coordinate real general\n2 2 2\n1 2 4\n")
+            .unwrap_err(),
+        error(
+            3,
+            1,
+            "",
+            ParseErrorKind::EdgeCountMismatch {
+                expected: 2,
+                found: 1
+            }
+        )
+    );
+    assert_eq!(
+        parse_matrix_market::<i32>("%%MatrixMarket matrix coordinate complex general\n")
+            .unwrap_err()
+            .kind,
+        ParseErrorKind::UnsupportedMatrix
+    );
+    assert_eq!(
+        parse_matrix_market::<i32>("%%MatrixMarket matrix coordinate real general\n2 3 0\n")
+            .unwrap_err(),
+        error(2, 1, "2 3 0", ParseErrorKind::UnsupportedMatrix)
+    );
+    assert_eq!(
+        parse_matrix_market::<i32>("%%MatrixMarket matrix coordinate real general\n2 2 1\n1 3 4\n")
+            .unwrap_err(),
+        error(3, 3, "3", ParseErrorKind::IndexOutOfRange { nodes: 2 })
+    );
+    assert_eq!(
+        parse_matrix_market::<i32>(
+            "%%MatrixMarket matrix coordinate real general\n4294967295 4294967295 0\n"
+        )
+        .unwrap_err(),
+        error(2, 1, "4294967295", ParseErrorKind::InvalidHeader)
+    );
+}
+
+#[test]
+fn format_is_detected_by_extension() {
+    for (path, format) in [
+        ("graph.gr", Format::Dimacs),
+        ("graph.DIMACS", Format::Dimacs),
+        ("graph.csv", Format::EdgeList),
+        ("graph.tsv", Format::EdgeList),
+        ("graph.edges", Format::EdgeList),
+        ("graph.el", Format::EdgeList),
+        ("graph.mtx", Format::MatrixMarket),
+    ] {
+        /* The extension wins over the content */
+        assert_eq!(Format::detect(path, "3\n(0, 1, 5)\n"), format, "{}", path);
+    }
+}
+
+#[test]
+fn format_is_detected_by_content() {
+    for (text, format) in [
+        ("3\n(0, 1, 5)\n", Format::Tuple),
+        ("# a triangle\n3 # nodes\n(0, 1, 5)\n", Format::Tuple),
+        ("", Format::Tuple),
+        ("c a graph\np sp 2 1\na 1 2 5\n", Format::Dimacs),
+        ("p sp 2 1\na 1 2 5\n", Format::Dimacs),
+        (
+            "%%MatrixMarket matrix coordinate real general\n",
+            Format::MatrixMarket,
+        ),
+        ("0 1 5\n", Format::EdgeList),
+        ("source,target,weight\n0,1,5\n", Format::EdgeList),
+        (
+            "# FromNodeId ToNodeId Weight\n# 3 nodes\n0 1 5\n1 2 3\n",
+            Format::EdgeList,
+        ),
+        ("% comment\n0 1 5\n", Format::EdgeList),
+    ] {
+        assert_eq!(Format::detect("graph.txt", text), format, "{:?}", text);
+        assert_eq!(Format::detect("-", text), format, "{:?}", text);
+    }
+}
+
+#[test]
+fn tuple_format_skips_blank_lines_and_comments() {
+    let input = "# a triangle\n\n3 # nodes\n\n(0, 1, 5) # first edge\n# (1, 2, 9)\n  (1, 2, 6)  \n";
+    let graph = parse_graph::<i32>(input).unwrap();
+    assert_eq!(graph.node_count(), 3);
+    assert_eq!(edges(&graph), vec![(0, 1, 5), (1, 2, 6)]);
+}
+
+#[test]
+fn tuple_format_errors_are_located() {
+    for (input, expected) in [
+        ("", error(1, 1, "", ParseErrorKind::MissingNodeCount)),
+        (
+            "# nothing\n\n",
+            error(2, 1, "", ParseErrorKind::MissingNodeCount),
+        ),
+        (
+            "  -3\n",
+            error(1, 3, "-3", ParseErrorKind::InvalidNodeCount),
+        ),
+        (
+            "4294967295\n(0,1,5)\n",
+            error(1, 1, "4294967295", ParseErrorKind::InvalidNodeCount),
+        ),
+        (
+            &format!("{}\n", MAX_NODES + 1),
+            error(
+                1,
+                1,
+                &(MAX_NODES + 1).to_string(),
+                ParseErrorKind::InvalidNodeCount,
+            ),
+        ),
+        (
+            "2\n(0, 1)\n",
+            error(2, 1, "(0, 1)", ParseErrorKind::MalformedTuple),
+        ),
+        (
+            "2\n  0, 1, 5\n",
+            error(2, 3, "0, 1, 5", ParseErrorKind::MalformedTuple),
+        ),
+        (
+            "2\n(0, x, 5)\n",
+            error(2, 5, "x", ParseErrorKind::InvalidNumber),
+        ),
+        (
+            "2\n(0, 1, five)\n",
+            error(2, 8, "five", ParseErrorKind::InvalidWeight),
+        ),
+        (
+            "2\n(-1, 1, 5)\n",
+            error(2, 2, "-1", ParseErrorKind::NegativeIndex),
+        ),
+        (
+            "2\n(0, 2, 5)\n",
+            error(2, 5, "2", ParseErrorKind::IndexOutOfRange { nodes: 2 }),
+        ),
+        (
+            "# header\n\n2\n\n(0, 1, 5)\n# (0, 9, 9)\n(1,  7, 5) # bad\n",
+            error(7, 6, "7", ParseErrorKind::IndexOutOfRange { nodes: 2 }),
+        ),
+    ] {
+        assert_eq!(
+            parse_graph::<i32>(input).unwrap_err(),
+            expected,
+            "{:?}",
+            input
+        );
+    }
+}
@@ -0,0 +1,32 @@
+use ghs::output::{format_mst, OutputFormat};
+use ghs::{kruskal, run_ghs, run_ghs_with, validate_graph, verify, Backend, Options};
+use petgraph::graph::Graph;
+use petgraph::Undirected;
+
+#[test]
+fn any_node_weight_is_accepted() {
+    let edges = [(0, 1, 4), (1, 2, 2), (2, 0, 7), (2, 3, 1)];
+
+    let mut unit: Graph<(), u64, Undirected> = Graph::default();
+    unit.extend_with_edges(edges.iter().map(|&(u, v, w)| (u, v, w as u64)));
+    validate_graph(&unit).unwrap();
+    let mst = run_ghs(&unit).unwrap();
+    assert!(verify(&mst, &kruskal(&unit)).edges_equal());
+    assert_eq!(mst.total_weight(), 7);
+
+    let mut named: Graph<String, i32, Undirected> = Graph::default();
+    for name in ["a", "b", "c", "d"] {
+        named.add_node(name.to_string());
+    }
+    named.extend_with_edges(edges);
+    let options = Options {
+        backend: Backend::Pool { threads: 2 },
+        ..Options::default()
+    };
+    let run = run_ghs_with(&named, &options).unwrap();
+    assert!(verify(&run.mst, &kruskal(&named)).edges_equal());
+    assert_eq!(
+        format_mst(OutputFormat::Tuple, &named, &run),
+        "(2, 3, 1)\n(1, 2, 2)\n(0, 1, 4)\n"
+    );
+}
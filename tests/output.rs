@@ -0,0 +1,112 @@
+use ghs::generate::{generate, Family};
+use ghs::output::{format_mst, OutputFormat};
+use ghs::{parse_graph_as, run_ghs, run_ghs_with, Format, Options, TotalF64, Weight};
+use petgraph::graph::Graph;
+use petgraph::visit::EdgeRef;
+use petgraph::Undirected;
+
+fn graph<W: Weight>(nodes: usize, edges: &[(u32, u32, W)]) -> Graph<(), W, Undirected> {
+    let mut graph = Graph::default();
+    for _ in 0..nodes {
+        graph.add_node(());
+    }
+    graph.extend_with_edges(edges);
+    graph
+}
+
+fn format<W: Weight>(format: OutputFormat, graph: &Graph<(), W, Undirected>) -> String {
+    let run = run_ghs_with(graph, &Options::default()).unwrap();
+    format_mst(format, graph, &run)
+}
+
+/// Edges of `graph` as sorted `(min, max, weight)` triples.
+fn edges<N>(graph: &Graph<N, i32, Undirected>) -> Vec<(usize, usize, i32)> {
+    let mut edges: Vec<(usize, usize, i32)> = graph
+        .edge_references()
+        .map(|edge| {
+            let (u, v) = (edge.source().index(), edge.target().index());
+            (u.min(v), u.max(v), *edge.weight())
+        })
+        .collect();
+    edges.sort_unstable();
+    edges
+}
+
+#[test]
+fn json_lists_counts_total_weight_and_edges_by_weight() {
+    let triangle = graph(3, &[(0, 1, 6), (1, 2, 5), (2, 0, 7)]);
+    assert_eq!(
+        format(OutputFormat::Json, &triangle),
+        "{\"node_count\":3,\"edge_count\":2,\"component_count\":1,\"total_weight\":11,\
+         \"edges\":[{\"source\":1,\"target\":2,\"weight\":5},{\"source\":0,\"target\":1,\"weight\":6}]}\n"
+    );
+
+    /* A forest: one component per connected part, isolated nodes included */
+    let forest = graph(4, &[(0, 1, -3)]);
+    assert_eq!(
+        format(OutputFormat::Json, &forest),
+        "{\"node_count\":4,\"edge_count\":1,\"component_count\":3,\"total_weight\":-3,\
+         \"edges\":[{\"source\":0,\"target\":1,\"weight\":-3}]}\n"
+    );
+}
+
+#[test]
+fn json_writes_infinite_weights_as_strings() {
+    let path = graph(
+        3,
+        &[(0, 1, TotalF64(-2.5)), (1, 2, TotalF64(f64::INFINITY))],
+    );
+    assert_eq!(
+        format(OutputFormat::Json, &path),
+        "{\"node_count\":3,\"edge_count\":2,\"component_count\":1,\"total_weight\":\"inf\",\
+         \"edges\":[{\"source\":0,\"target\":1,\"weight\":-2.5},{\"source\":1,\"target\":2,\"weight\":\"inf\"}]}\n"
+    );
+    let path = graph(2, &[(0, 1, TotalF64(f64::NEG_INFINITY))]);
+    assert!(format(OutputFormat::Json, &path).contains("\"weight\":\"-inf\""));
+}
+
+#[test]
+fn csv_has_a_header_and_one_edge_per_line() {
+    let triangle = graph(3, &[(0, 1, 6), (1, 2, 5), (2, 0, 7)]);
+    assert_eq!(
+        format(OutputFormat::Csv, &triangle),
+        "source,target,weight\n1,2,5\n0,1,6\n"
+    );
+}
+
+#[test]
+fn csv_reads_back_as_an_edge_list() {
+    let graph = generate(Family::TreeChords, 40, 4.0, 11).to_graph();
+    let run = run_ghs_with(&graph, &Options::default()).unwrap();
+    let csv = format_mst(OutputFormat::Csv, &graph, &run);
+
+    let tree = parse_graph_as::<i32>(&csv, Format::EdgeList).unwrap();
+    assert_eq!(tree.node_count(), graph.node_count());
+    let mut expected: Vec<(usize, usize, i32)> = run
+        .mst
+        .edges
+        .iter()
+        .map(|edge| {
+            let (u, v) = (edge.source.index(), edge.target.index());
+            (u.min(v), u.max(v), edge.weight)
+        })
+        .collect();
+    expected.sort_unstable();
+    assert_eq!(edges(&tree), expected);
+    /* A spanning tree is its own minimum spanning tree */
+    assert_eq!(run_ghs(&tree).unwrap(), run.mst);
+}
+
+#[test]
+fn dot_highlights_the_tree_edges() {
+    let triangle = graph(3, &[(0, 1, 6), (1, 2, 5), (2, 0, 7)]);
+    let dot = format(OutputFormat::Dot, &triangle);
+    assert!(dot.starts_with("graph ghs {\n"), "{}", dot);
+    for line in [
+        "    0 -- 1 [label=\"6\", style=bold, color=red, penwidth=2];",
+        "    1 -- 2 [label=\"5\", style=bold, color=red, penwidth=2];",
+        "    2 -- 0 [label=\"7\", style=dashed, color=gray];",
+    ] {
+        assert!(dot.lines().any(|l| l == line), "{}", dot);
+    }
+}
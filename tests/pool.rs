@@ -0,0 +1,30 @@
+use ghs::generate::{generate, Family};
+use ghs::{kruskal, run_ghs_with, verify, Backend, Options};
+
+#[test]
+fn pool_matches_kruskal_for_any_worker_count() {
+    for (seed, threads) in [(1, 1), (2, 2), (3, 3), (4, 8)] {
+        let graph = generate(Family::ErdosRenyi, 300, 5.0, seed).to_graph();
+        let options = Options {
+            backend: Backend::Pool { threads },
+            ..Options::default()
+        };
+        let run = run_ghs_with(&graph, &options).unwrap();
+        let verification = verify(&run.mst, &kruskal(&graph));
+        assert!(verification.edges_equal(), "{}", verification);
+        assert!(run.nodes.values().all(|node| node.halted));
+    }
+}
+
+#[test]
+fn pool_handles_more_workers_than_nodes_and_isolated_nodes() {
+    let mut graph = generate(Family::Ring, 3, 0.0, 0).to_graph();
+    graph.add_node(0);
+    let options = Options {
+        backend: Backend::Pool { threads: 16 },
+        ..Options::default()
+    };
+    let run = run_ghs_with(&graph, &options).unwrap();
+    assert_eq!(run.mst.components.len(), 2);
+    assert_eq!(run.mst.edges.len(), 2);
+}
@@ -0,0 +1,101 @@
+use ghs::generate::{generate, Family};
+use ghs::{run_ghs_with, Backend, Options, Replay, ReplayErrorKind, Tracer};
+use std::io;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+/// A writer whose bytes stay readable after the tracer that owns it is done.
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Trace of a complete run on a 12-node graph.
+fn record(backend: Backend) -> String {
+    let graph = generate(Family::ErdosRenyi, 12, 4.0, 7).to_graph();
+    let buffer = Buffer::default();
+    let options = Options {
+        backend,
+        trace: Some(Arc::new(Tracer::new(buffer.clone()))),
+        ..Options::default()
+    };
+    run_ghs_with(&graph, &options).unwrap();
+    options.trace.unwrap().finish().unwrap();
+    let bytes = buffer.0.lock().unwrap().clone();
+    String::from_utf8(bytes).unwrap()
+}
+
+/// Replays `trace` until it ends or fails.
+fn replay_all(trace: &str) -> Result<Replay<i32>, ghs::ReplayError<i32>> {
+    let mut replay = Replay::<i32>::from_trace(trace)?;
+    while replay.step()?.is_some() {}
+    Ok(replay)
+}
+
+/// `trace` with the first line containing `event` passed through `tamper`,
+/// and the 1-based number of that line.
+fn tampered(trace: &str, event: &str, tamper: impl Fn(&str) -> String) -> (String, usize) {
+    let mut lines: Vec<String> = trace.lines().map(String::from).collect();
+    let index = lines.iter().position(|line| line.contains(event)).unwrap();
+    let changed = tamper(&lines[index]);
+    assert_ne!(changed, lines[index]);
+    lines[index] = changed;
+    (lines.join("\n"), index + 1)
+}
+
+#[test]
+fn recorded_runs_replay_to_the_end() {
+    for backend in [
+        Backend::Threads,
+        Backend::Pool { threads: 3 },
+        Backend::Simulated { seed: 4 },
+    ] {
+        let replay = replay_all(&record(backend)).unwrap();
+        assert!(replay.is_complete());
+        assert_eq!(replay.position(), replay.steps().len());
+        assert!(replay.nodes().iter().all(|node| node.is_halted()));
+    }
+}
+
+#[test]
+fn tampered_send_diverges() {
+    let trace = record(Backend::Simulated { seed: 4 });
+    let (trace, line) = tampered(&trace, "\"type\":\"Connect\"", |line| {
+        line.replacen(
+            "\"type\":\"Connect\",\"level\":0",
+            "\"type\":\"Connect\",\"level\":3",
+            1,
+        )
+    });
+    let err = replay_all(&trace).err().unwrap();
+    assert_eq!(err.line, line);
+    assert!(
+        matches!(err.kind, ReplayErrorKind::Diverged { .. }),
+        "{:?}",
+        err
+    );
+}
+
+#[test]
+fn tampered_state_is_a_mismatch() {
+    let trace = record(Backend::Simulated { seed: 4 });
+    let (trace, line) = tampered(&trace, "\"event\":\"init\"", |line| {
+        line.replacen("\"state\":\"Sleep\"", "\"state\":\"Find\"", 1)
+    });
+    let err = replay_all(&trace).err().unwrap();
+    assert_eq!(err.line, line);
+    assert!(
+        matches!(err.kind, ReplayErrorKind::StateMismatch { .. }),
+        "{:?}",
+        err
+    );
+}
@@ -0,0 +1,375 @@
+use ghs::generate::{generate, Family, Generated};
+use ghs::rng::Rng;
+use ghs::{
+    kruskal, run_ghs_scheduled, run_ghs_with, validate_graph, Backend, Link, Options, Run,
+    Scheduler, Status,
+};
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use petgraph::Undirected;
+use std::collections::BTreeSet;
+
+/// Order in which the simulated network delivers messages.
+#[derive(Clone, Debug)]
+enum Schedule {
+    /// A uniformly random link.
+    Random(u64),
+    /// The link sent on most recently, so new traffic overtakes older traffic on other links.
+    Lifo,
+    /// A random link, but messages to the node only when nothing else is pending.
+    DelayNode(usize, u64),
+    /// A random link, but the link `from -> to` only when nothing else is pending.
+    StarveLink(usize, usize, u64),
+    /// Recorded picks, reduced modulo the number of pending links; the first
+    /// pending link once they run out.
+    Picks(Vec<usize>),
+}
+
+/// Follows a `Schedule`, recording its picks and ending the run after `budget` deliveries.
+struct Adversary {
+    schedule: Schedule,
+    rng: Rng,
+    budget: usize,
+    picks: Vec<usize>,
+}
+
+impl Adversary {
+    fn new(schedule: Schedule, budget: usize) -> Self {
+        let seed = match schedule {
+            Schedule::Random(seed)
+            | Schedule::DelayNode(_, seed)
+            | Schedule::StarveLink(_, _, seed) => seed,
+            Schedule::Lifo | Schedule::Picks(_) => 0,
+        };
+        Adversary {
+            schedule,
+            rng: Rng::new(seed),
+            budget,
+            picks: vec![],
+        }
+    }
+}
+
+impl Scheduler for Adversary {
+    fn pick(&mut self, pending: &[Link]) -> Option<usize> {
+        if self.picks.len() == self.budget {
+            return None;
+        }
+        let slow = |link: &Link| match self.schedule {
+            Schedule::DelayNode(node, _) => link.to.index() == node,
+            Schedule::StarveLink(from, to, _) => (link.from.index(), link.to.index()) == (from, to),
+            _ => false,
+        };
+        let fast: Vec<usize> = (0..pending.len())
+            .filter(|&slot| !slow(&pending[slot]))
+            .collect();
+        let pick = match &self.schedule {
+            Schedule::Lifo => (0..pending.len())
+                .max_by_key(|&slot| pending[slot].last_sent)
+                .expect("pending is never empty"),
+            Schedule::Picks(picks) => picks
+                .get(self.picks.len())
+                .map_or(0, |pick| pick % pending.len()),
+            _ if fast.is_empty() => self.rng.below(pending.len()),
+            _ => fast[self.rng.below(fast.len())],
+        };
+        self.picks.push(pick);
+        Some(pick)
+    }
+}
+
+type Property = fn(&Graph<i32, i32, Undirected>, &Run<i32>) -> Result<(), String>;
+
+/// What must hold after every run: all nodes halt, the `Branch` edges are the
+/// minimum spanning tree, and at most 5 N log N + 2 E protocol messages are sent.
+fn ghs_properties(graph: &Graph<i32, i32, Undirected>, run: &Run<i32>) -> Result<(), String> {
+    let mut running: Vec<usize> = graph
+        .node_indices()
+        .filter(|node_index| !run.nodes[node_index].halted)
+        .map(NodeIndex::index)
+        .collect();
+    running.sort_unstable();
+    if !running.is_empty() {
+        return Err(format!("nodes {:?} never halted", running));
+    }
+
+    let status = |from: NodeIndex, to: NodeIndex| run.nodes[&from].status.get(&to).copied();
+    let mut branches = BTreeSet::new();
+    for edge in graph.edge_references() {
+        let (u, v) = (edge.source(), edge.target());
+        let ends = (status(u, v), status(v, u));
+        match ends {
+            (Some(Status::Branch), Some(Status::Branch)) => {
+                branches.insert((u.index(), v.index()));
+            }
+            (Some(Status::Branch), _) | (_, Some(Status::Branch)) => {
+                return Err(format!(
+                    "edge {}-{} is Branch at one end only: {:?}",
+                    u.index(),
+                    v.index(),
+                    ends
+                ))
+            }
+            _ => {}
+        }
+    }
+    let expected: BTreeSet<(usize, usize)> = kruskal(graph)
+        .edges
+        .iter()
+        .map(|edge| (edge.source.index(), edge.target.index()))
+        .map(|(u, v)| (u.min(v), u.max(v)))
+        .collect();
+    let branches: BTreeSet<(usize, usize)> = branches
+        .into_iter()
+        .map(|(u, v)| (u.min(v), u.max(v)))
+        .collect();
+    if branches != expected {
+        return Err(format!(
+            "Branch edges {:?}, minimum spanning tree {:?}",
+            branches, expected
+        ));
+    }
+
+    let (n, e) = (graph.node_count() as f64, graph.edge_count() as f64);
+    let bound = 5.0 * n * n.log2() + 2.0 * e;
+    if run.stats.messages() as f64 > bound {
+        return Err(format!(
+            "{} messages exceed the bound of {}",
+            run.stats.messages(),
+            bound
+        ));
+    }
+    Ok(())
+}
+
+/// Runs `graph` under `schedule`; on a violated `property`, returns the
+/// violation and the picks made.
+fn check(
+    graph: &Generated,
+    schedule: &Schedule,
+    property: Property,
+) -> Result<(), (String, Vec<usize>)> {
+    let graph = graph.to_graph();
+    let (n, e) = (graph.node_count(), graph.edge_count());
+    /* Far more deliveries than the message bound allows means the run does not terminate */
+    let budget = 10 * (5 * n * n + 2 * e + n);
+    let mut adversary = Adversary::new(schedule.clone(), budget);
+    let run = run_ghs_scheduled(&graph, &Options::default(), &mut adversary)
+        .expect("Error: test graph is invalid:");
+    property(&graph, &run).map_err(|err| (err, adversary.picks))
+}
+
+/// Shrinks a failing graph and its picks while `property` keeps failing: first
+/// by dropping edges and nodes (the graph staying connected), then by
+/// shortening the picks and lowering each of them.
+fn shrink(
+    mut graph: Generated,
+    mut picks: Vec<usize>,
+    mut err: String,
+    property: Property,
+) -> (Generated, Vec<usize>, String) {
+    'shrinking: loop {
+        for candidate in smaller_graphs(&graph) {
+            if validate_graph(&candidate.to_graph()).is_err() {
+                continue;
+            }
+            if let Err((candidate_err, _)) =
+                check(&candidate, &Schedule::Picks(picks.clone()), property)
+            {
+                graph = candidate;
+                err = candidate_err;
+                continue 'shrinking;
+            }
+        }
+        for candidate in smaller_picks(&picks) {
+            if let Err((candidate_err, _)) =
+                check(&graph, &Schedule::Picks(candidate.clone()), property)
+            {
+                picks = candidate;
+                err = candidate_err;
+                continue 'shrinking;
+            }
+        }
+        break;
+    }
+
+    /* Picks past the end default to 0, and only the order of the weights matters */
+    while picks.last() == Some(&0) {
+        picks.pop();
+    }
+    let mut order: Vec<usize> = (0..graph.edges.len()).collect();
+    order.sort_by_key(|&i| graph.edges[i].2);
+    for (rank, i) in order.into_iter().enumerate() {
+        graph.edges[i].2 = rank as i32 + 1;
+    }
+    (graph, picks, err)
+}
+
+fn smaller_graphs(graph: &Generated) -> Vec<Generated> {
+    let mut candidates = vec![];
+    for node in 0..graph.nodes {
+        /* Drop the node and renumber the ones above it */
+        let renumber = |u: u32| if u > node { u - 1 } else { u };
+        candidates.push(Generated {
+            nodes: graph.nodes - 1,
+            edges: graph
+                .edges
+                .iter()
+                .filter(|&&(u, v, _)| u != node && v != node)
+                .map(|&(u, v, w)| (renumber(u), renumber(v), w))
+                .collect(),
+        });
+    }
+    for i in 0..graph.edges.len() {
+        let mut edges = graph.edges.clone();
+        edges.remove(i);
+        candidates.push(Generated {
+            nodes: graph.nodes,
+            edges,
+        });
+    }
+    candidates
+}
+
+fn smaller_picks(picks: &[usize]) -> Vec<Vec<usize>> {
+    if picks.is_empty() {
+        return vec![];
+    }
+    let mut candidates = vec![
+        picks[..picks.len() / 2].to_vec(),
+        picks[..picks.len() - 1].to_vec(),
+    ];
+    for i in 0..picks.len() {
+        if picks[i] > 0 {
+            for lower in [0, picks[i] - 1] {
+                let mut candidate = picks.to_vec();
+                candidate[i] = lower;
+                candidates.push(candidate);
+            }
+        }
+    }
+    candidates
+}
+
+/// Checks `property` on random graphs under every kind of schedule, panicking
+/// with a shrunk counterexample.
+fn check_random_cases(cases: usize, seed: u64, property: Property) {
+    let families = [
+        Family::ErdosRenyi,
+        Family::Geometric,
+        Family::Grid,
+        Family::Torus,
+        Family::Complete,
+        Family::Ring,
+        Family::Star,
+        Family::TreeChords,
+        Family::BarabasiAlbert,
+    ];
+    let mut rng = Rng::new(seed);
+    for _ in 0..cases {
+        let family = families[rng.below(families.len())];
+        let nodes = 2 + rng.below(23) as u32;
+        let degree = 1.0 + rng.below(8) as f64;
+        let graph = generate(family, nodes, degree, rng.next_u64());
+        let (u, v, _) = graph.edges[rng.below(graph.edges.len())];
+        let (from, to) = if rng.below(2) == 0 { (u, v) } else { (v, u) };
+        let schedules = [
+            Schedule::Random(rng.next_u64()),
+            Schedule::Lifo,
+            Schedule::DelayNode(rng.below(nodes as usize), rng.next_u64()),
+            Schedule::StarveLink(from as usize, to as usize, rng.next_u64()),
+        ];
+        for schedule in &schedules {
+            if let Err((err, picks)) = check(&graph, schedule, property) {
+                let (shrunk, picks, shrunk_err) =
+                    shrink(graph.clone(), picks, err.clone(), property);
+                panic!(
+                    "{} graph with {} nodes and {} edges under {:?}: {}\n\
+                     shrunk to: {}\nwith picks {:?} on\n{}",
+                    family,
+                    graph.nodes,
+                    graph.edges.len(),
+                    schedule,
+                    err,
+                    shrunk_err,
+                    picks,
+                    shrunk.to_tuple_format()
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn random_graphs_under_adversarial_schedules() {
+    check_random_cases(200, 1, ghs_properties);
+}
+
+#[test]
+fn tie_breaking_matches_kruskal_on_repeated_weights() {
+    let mut rng = Rng::new(5);
+    for _ in 0..40 {
+        let nodes = 2 + rng.below(23) as u32;
+        let mut generated = generate(Family::ErdosRenyi, nodes, 4.0, rng.next_u64());
+        /* Only three distinct weights, so most comparisons are decided by the node indices */
+        for edge in &mut generated.edges {
+            edge.2 = edge.2 % 3 + 1;
+        }
+        let graph = generated.to_graph();
+        let options = Options {
+            tie_break: true,
+            ..Options::default()
+        };
+        let backends = [
+            Backend::Threads,
+            Backend::Pool { threads: 3 },
+            Backend::Simulated {
+                seed: rng.next_u64(),
+            },
+        ];
+        for backend in backends {
+            let options = Options {
+                backend,
+                ..options.clone()
+            };
+            let run = run_ghs_with(&graph, &options).expect("Error: test graph is invalid:");
+            if let Err(err) = ghs_properties(&graph, &run) {
+                panic!("{:?}: {}\n{}", backend, err, generated.to_tuple_format());
+            }
+        }
+        let (u, v, _) = generated.edges[rng.below(generated.edges.len())];
+        let schedules = [
+            Schedule::Random(rng.next_u64()),
+            Schedule::Lifo,
+            Schedule::DelayNode(rng.below(nodes as usize), rng.next_u64()),
+            Schedule::StarveLink(u as usize, v as usize, rng.next_u64()),
+        ];
+        let budget = 10 * (5 * graph.node_count().pow(2) + 2 * graph.edge_count());
+        for schedule in schedules {
+            let mut adversary = Adversary::new(schedule.clone(), budget);
+            let run = run_ghs_scheduled(&graph, &options, &mut adversary)
+                .expect("Error: test graph is invalid:");
+            if let Err(err) = ghs_properties(&graph, &run) {
+                panic!("{:?}: {}\n{}", schedule, err, generated.to_tuple_format());
+            }
+        }
+    }
+}
+
+#[test]
+fn failures_shrink_to_a_minimal_case() {
+    /* Holds only on graphs whose spanning tree has fewer than 3 edges */
+    fn small_tree(_: &Graph<i32, i32, Undirected>, run: &Run<i32>) -> Result<(), String> {
+        match run.mst.edges.len() {
+            edges if edges < 3 => Ok(()),
+            edges => Err(format!("{} tree edges", edges)),
+        }
+    }
+    let graph = generate(Family::ErdosRenyi, 12, 4.0, 9);
+    let (err, picks) = check(&graph, &Schedule::Random(3), small_tree).unwrap_err();
+    let (shrunk, picks, err) = shrink(graph, picks, err, small_tree);
+    assert_eq!(shrunk.nodes, 4);
+    assert_eq!(shrunk.edges.len(), 3);
+    assert_eq!(picks, Vec::<usize>::new());
+    assert_eq!(err, "3 tree edges");
+}
@@ -0,0 +1,107 @@
+use ghs::generate::{generate, Family};
+use ghs::{run_ghs_with, Backend, LevelSnapshot, Options, Run, Status};
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::Undirected;
+use std::collections::BTreeSet;
+
+fn run_with_snapshots(graph: &Graph<i32, i32, Undirected>, backend: Backend) -> Run<i32> {
+    let options = Options {
+        backend,
+        snapshots: true,
+        ..Options::default()
+    };
+    run_ghs_with(graph, &options).unwrap()
+}
+
+/// Nodes carrying the level and name of `snapshot`.
+fn carriers(snapshot: &LevelSnapshot<i32>) -> BTreeSet<NodeIndex> {
+    snapshot
+        .nodes
+        .iter()
+        .filter(|(_, node)| node.level == snapshot.level && node.name == Some(snapshot.name))
+        .map(|(&node_index, _)| node_index)
+        .collect()
+}
+
+/// Nodes joined to `start` by edges that both ends mark `Branch`.
+fn fragment(snapshot: &LevelSnapshot<i32>, start: NodeIndex) -> BTreeSet<NodeIndex> {
+    let branch = |from: NodeIndex, to: NodeIndex| {
+        snapshot
+            .nodes
+            .get(&from)
+            .and_then(|node| node.status.get(&to))
+            == Some(&Status::Branch)
+    };
+    let mut members = BTreeSet::from([start]);
+    let mut stack = vec![start];
+    while let Some(node_index) = stack.pop() {
+        for &nbr in snapshot.nodes[&node_index].status.keys() {
+            if branch(node_index, nbr) && branch(nbr, node_index) && members.insert(nbr) {
+                stack.push(nbr);
+            }
+        }
+    }
+    members
+}
+
+#[test]
+fn every_member_of_a_snapshot_fragment_carries_its_level_and_name() {
+    for seed in 0..5 {
+        let graph = generate(Family::ErdosRenyi, 60, 4.0, seed).to_graph();
+        for backend in [
+            Backend::Threads,
+            Backend::Pool { threads: 3 },
+            Backend::Simulated { seed },
+        ] {
+            let run = run_with_snapshots(&graph, backend);
+            assert!(run.snapshots.len() > 1, "{:?}", backend);
+            for snapshot in &run.snapshots {
+                let carriers = carriers(snapshot);
+                let start = *carriers.iter().next().expect("a carrier");
+                assert_eq!(fragment(snapshot, start), carriers, "{:?}", backend);
+            }
+            /* The last snapshot is the end of the run */
+            let last = run.snapshots.last().unwrap();
+            assert_eq!(last.nodes, run.nodes);
+            assert_eq!(carriers(last).len(), graph.node_count());
+        }
+    }
+}
+
+#[test]
+fn absorbs_are_captured() {
+    /* The edge 2-3 is the only core: every other node joins its fragment by an absorb */
+    let mut graph: Graph<i32, i32, Undirected> = Graph::default();
+    for _ in 0..5 {
+        graph.add_node(0);
+    }
+    graph.extend_with_edges([
+        (0, 1, -3),
+        (0, 2, -4),
+        (0, 3, 5),
+        (0, 4, 9),
+        (1, 2, -2),
+        (1, 3, 4),
+        (1, 4, 7),
+        (2, 3, -9),
+        (2, 4, 1),
+        (3, 4, 3),
+    ]);
+    let run = run_with_snapshots(&graph, Backend::Simulated { seed: 3 });
+    let (end, merges) = run.snapshots.split_last().unwrap();
+    assert!(end.nodes.values().all(|node| node.halted));
+    let sizes: Vec<usize> = merges
+        .iter()
+        .map(|snapshot| {
+            assert_eq!(snapshot.level, 1);
+            carriers(snapshot).len()
+        })
+        .collect();
+    assert!(sizes.len() > 1, "{:?}", sizes);
+    assert!(
+        sizes.windows(2).all(|pair| pair[0] < pair[1]),
+        "{:?}",
+        sizes
+    );
+    assert_eq!(sizes.last(), Some(&5));
+}
@@ -0,0 +1,120 @@
+use ghs::{kruskal, run_ghs, run_ghs_with, validate_graph, verify, Options, Problem};
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::Undirected;
+
+fn graph(nodes: usize, edges: &[(u32, u32, i32)]) -> Graph<(), i32, Undirected> {
+    let mut graph = Graph::default();
+    for _ in 0..nodes {
+        graph.add_node(());
+    }
+    graph.extend_with_edges(edges);
+    graph
+}
+
+fn n(index: usize) -> NodeIndex {
+    NodeIndex::new(index)
+}
+
+fn problems(graph: &Graph<(), i32, Undirected>) -> Vec<Problem<i32>> {
+    validate_graph(graph).unwrap_err().problems
+}
+
+#[test]
+fn valid_graph_has_no_problems() {
+    assert!(validate_graph(&graph(3, &[(0, 1, 1), (1, 2, 2), (2, 0, 3)])).is_ok());
+}
+
+#[test]
+fn duplicate_weights_list_their_edges() {
+    assert_eq!(
+        problems(&graph(3, &[(0, 1, 5), (2, 1, 5), (2, 0, 6)])),
+        vec![Problem::DuplicateWeight {
+            weight: 5,
+            edges: vec![(n(0), n(1)), (n(1), n(2))],
+        }]
+    );
+}
+
+#[test]
+fn self_loops_are_reported_with_their_weight() {
+    assert_eq!(
+        problems(&graph(2, &[(0, 1, 1), (1, 1, 2)])),
+        vec![Problem::SelfLoop {
+            node: n(1),
+            weight: 2,
+        }]
+    );
+}
+
+#[test]
+fn parallel_edges_list_their_weights() {
+    assert_eq!(
+        problems(&graph(2, &[(0, 1, 1), (1, 0, 2)])),
+        vec![Problem::ParallelEdges {
+            source: n(0),
+            target: n(1),
+            weights: vec![1, 2],
+        }]
+    );
+}
+
+#[test]
+fn isolated_nodes_and_components_are_listed() {
+    assert_eq!(
+        problems(&graph(3, &[(0, 1, 1)])),
+        vec![
+            Problem::IsolatedNode(n(2)),
+            Problem::Disconnected {
+                components: vec![vec![n(0), n(1)], vec![n(2)]],
+            },
+        ]
+    );
+    assert_eq!(
+        problems(&graph(4, &[(3, 2, 2), (0, 1, 1)])),
+        vec![Problem::Disconnected {
+            components: vec![vec![n(0), n(1)], vec![n(2), n(3)]],
+        }]
+    );
+    /* A node whose only edge is a self-loop is isolated too */
+    assert_eq!(
+        problems(&graph(3, &[(0, 1, 1), (2, 2, 3)])),
+        vec![
+            Problem::SelfLoop {
+                node: n(2),
+                weight: 3,
+            },
+            Problem::IsolatedNode(n(2)),
+            Problem::Disconnected {
+                components: vec![vec![n(0), n(1)], vec![n(2)]],
+            },
+        ]
+    );
+}
+
+#[test]
+fn run_ghs_refuses_what_the_protocol_cannot_handle() {
+    /* Isolated nodes and components are fine: the result is a forest */
+    let forest = run_ghs(&graph(5, &[(0, 1, 1), (2, 3, 2)])).unwrap();
+    assert_eq!(forest.components.len(), 3);
+
+    let self_loop = graph(2, &[(0, 1, 1), (1, 1, 2)]);
+    let parallel = graph(2, &[(0, 1, 1), (1, 0, 2)]);
+    let duplicate = graph(3, &[(0, 1, 5), (1, 2, 5), (2, 0, 6)]);
+    for graph in [&self_loop, &parallel, &duplicate] {
+        assert_eq!(run_ghs(graph).unwrap_err().problems, problems(graph));
+    }
+
+    /* Tie-breaking lets repeated weights through, and only those */
+    let tie_break = Options {
+        tie_break: true,
+        ..Options::default()
+    };
+    let run = run_ghs_with(&duplicate, &tie_break).unwrap();
+    assert!(verify(&run.mst, &kruskal(&duplicate)).edges_equal());
+    for graph in [&self_loop, &parallel] {
+        assert_eq!(
+            run_ghs_with(graph, &tie_break).unwrap_err().problems,
+            problems(graph)
+        );
+    }
+}